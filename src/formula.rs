@@ -0,0 +1,807 @@
+//! Formulas are small expressions over the other cells in a row, like
+//! `if(Finished, "done", days_until(Date))`.
+//!
+//! Columns are used by name, names that aren't a single word are written `[Due Date]` or
+//! `prop("Due Date")`. There are numbers, "strings", `true`/`false`, `today`, the operators
+//! `+ - * / %`, `== != < <= > >=`, `and`, `or` and `not`, and the functions listed in `call`.
+//! Adding a number to a date adds that many days, and numbers can be written as durations like
+//! `7d` or `2w`, which are that many days.
+
+use crate::{Cell, DATE_FORMAT};
+use chrono::prelude::*;
+use chrono::{NaiveDate, TimeDelta};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    Ident(String),
+    Column(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(num) => write!(f, "'{}'", num),
+            Token::Str(text) => write!(f, "'\"{}\"'", text),
+            Token::Ident(name) => write!(f, "'{}'", name),
+            Token::Column(name) => write!(f, "'[{}]'", name),
+            Token::Op(op) => write!(f, "'{}'", op),
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+            Token::Comma => write!(f, "','"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Literal(Cell),
+    Column(String),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+/// Longest first, so `<=` isn't read as `<` then `=`.
+const OPERATORS: [&str; 14] = [
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "+", "-", "*", "/", "%", "!",
+];
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    Ok(spanned_tokens(src)?
+        .into_iter()
+        .map(|(token, _)| token)
+        .collect())
+}
+
+/// The tokens of `src`, each with the range of chars it was read from.
+fn spanned_tokens(src: &str) -> Result<Vec<(Token, Range<usize>)>, String> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens: Vec<Token> = vec![];
+    let mut spans: Vec<Range<usize>> = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let token_start = i;
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit()
+            || (c == '.' && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()))
+        {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let mut num = text
+                .parse::<f64>()
+                .map_err(|_| format!("'{}' is not a number", text))?;
+            // durations, in days
+            let unit = chars.get(i).copied();
+            let unit_ends = chars
+                .get(i + 1)
+                .is_none_or(|c| !c.is_alphanumeric() && *c != '_');
+            match unit {
+                Some('d') if unit_ends => i += 1,
+                Some('w') if unit_ends => {
+                    num *= 7.0;
+                    i += 1;
+                }
+                _ => {}
+            }
+            tokens.push(Token::Number(num));
+        } else if c == '"' {
+            let mut text = "".to_string();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    Some('"') => break,
+                    Some('\\') if i + 1 < chars.len() => {
+                        text.push(chars[i + 1]);
+                        i += 2;
+                    }
+                    Some(c) => {
+                        text.push(*c);
+                        i += 1;
+                    }
+                    None => return Err("a string is missing its closing '\"'".to_string()),
+                }
+            }
+            i += 1;
+            tokens.push(Token::Str(text));
+        } else if c == '[' {
+            let end = chars[i..]
+                .iter()
+                .position(|c| *c == ']')
+                .ok_or("a column is missing its closing ']'")?;
+            tokens.push(Token::Column(chars[i + 1..i + end].iter().collect()));
+            i += end + 1;
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else if c == ',' {
+            tokens.push(Token::Comma);
+            i += 1;
+        } else {
+            let rest: String = chars[i..].iter().collect();
+            match OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+                Some(op) => {
+                    tokens.push(Token::Op(op));
+                    i += op.len();
+                }
+                None => return Err(format!("unexpected '{}'", c)),
+            }
+        }
+        if spans.len() < tokens.len() {
+            spans.push(token_start..i);
+        }
+    }
+
+    Ok(tokens.into_iter().zip(spans).collect())
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), String> {
+        match self.next() {
+            Some(next) if next == token => Ok(()),
+            Some(next) => Err(format!("expected {} but found {}", token, next)),
+            None => Err(format!("expected {} at the end", token)),
+        }
+    }
+
+    /// Whether the next token is `op`, or the word for it, like `and` for `&&`.
+    fn next_is_op(&self, ops: &[&str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => Some(op),
+            Some(Token::Ident(word)) => match (word.to_lowercase().as_str(), ops) {
+                ("and", ops) if ops.contains(&"&&") => Some("&&"),
+                ("or", ops) if ops.contains(&"||") => Some("||"),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn binary(
+        &mut self,
+        ops: &[&str],
+        operand: fn(&mut Parser) -> Result<Expr, String>,
+    ) -> Result<Expr, String> {
+        let mut lhs = operand(self)?;
+        while let Some(op) = self.next_is_op(ops) {
+            self.pos += 1;
+            let rhs = operand(self)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        self.binary(&["||"], Parser::and)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        self.binary(&["&&"], Parser::not)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some(Token::Op("!")) => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.not()?)))
+            }
+            Some(Token::Ident(word))
+                if word.eq_ignore_ascii_case("not")
+                    && self.tokens.get(self.pos + 1) != Some(&Token::LParen) =>
+            {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.not()?)))
+            }
+            _ => self.comparison(),
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        self.binary(&["==", "!=", "<", "<=", ">", ">="], Parser::sum)
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        self.binary(&["+", "-"], Parser::product)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        self.binary(&["*", "/", "%"], Parser::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some(&Token::Op("-")) {
+            self.pos += 1;
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(num)) => Ok(Expr::Literal(Cell::number(num))),
+            Some(Token::Str(text)) if text.is_empty() => Ok(Expr::Literal(Cell::Empty)),
+            Some(Token::Str(text)) => Ok(Expr::Literal(Cell::Text(text))),
+            Some(Token::Column(name)) => Ok(Expr::Column(name)),
+            Some(Token::LParen) => {
+                let expr = self.or()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                self.pos += 1;
+                let mut args: Vec<Expr> = vec![];
+                if self.peek() != Some(&Token::RParen) {
+                    args.push(self.or()?);
+                    while self.peek() == Some(&Token::Comma) {
+                        self.pos += 1;
+                        args.push(self.or()?);
+                    }
+                }
+                self.expect(Token::RParen)?;
+                Ok(Expr::Call(name.to_lowercase(), args))
+            }
+            Some(Token::Ident(name)) => Ok(match name.to_lowercase().as_str() {
+                "true" => Expr::Literal(Cell::Boolean(true)),
+                "false" => Expr::Literal(Cell::Boolean(false)),
+                "today" => Expr::Call("today".to_string(), vec![]),
+                _ => Expr::Column(name),
+            }),
+            Some(token) => Err(format!("unexpected {}", token)),
+            None => Err("the formula ends too soon".to_string()),
+        }
+    }
+}
+
+pub fn parse(src: &str) -> Result<Expr, String> {
+    let mut parser = Parser {
+        tokens: tokenize(src)?,
+        pos: 0,
+    };
+    let expr = parser.or()?;
+    match parser.next() {
+        None => Ok(expr),
+        Some(token) => Err(format!("unexpected {}", token)),
+    }
+}
+
+pub fn is_truthy(value: &Cell) -> bool {
+    match value {
+        Cell::Empty | Cell::Invalid(_) => false,
+        Cell::Boolean(b) => *b,
+        Cell::Number(num, _) => *num != 0.0,
+        Cell::Options(options) => !options.is_empty(),
+        Cell::Relation(ids) => !ids.is_empty(),
+        _ => true,
+    }
+}
+
+fn to_text(value: &Cell) -> String {
+    match value {
+        Cell::Boolean(b) => b.to_string(),
+        Cell::Date(date) => date.format(DATE_FORMAT).to_string(),
+        _ => value.to_string(),
+    }
+}
+
+fn to_number(value: &Cell) -> Result<f64, String> {
+    match value {
+        Cell::Number(num, _) => Ok(*num),
+        Cell::Boolean(b) => Ok(if *b { 1.0 } else { 0.0 }),
+        Cell::Empty => Ok(0.0),
+        Cell::Text(text) => text
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| format!("'{}' is not a number", text)),
+        _ => Err(format!("'{}' is not a number", value)),
+    }
+}
+
+/// The day a value is on, ranges are on their first day.
+fn to_date(value: &Cell) -> Result<NaiveDate, String> {
+    match value {
+        Cell::Date(date) => Ok(*date),
+        Cell::DateTime(date_time) => Ok(date_time.with_timezone(&Local).date_naive()),
+        Cell::DateRange(start, _) => Ok(*start),
+        _ => Err(format!("'{}' is not a date", value)),
+    }
+}
+
+fn is_date(value: &Cell) -> bool {
+    matches!(
+        value,
+        Cell::Date(_) | Cell::DateTime(_) | Cell::DateRange(..)
+    )
+}
+
+fn add_days(value: &Cell, days: f64) -> Result<Cell, String> {
+    let out_of_range = || format!("{} days from {} is out of range", days, to_text(value));
+    let add = |date: NaiveDate, duration: TimeDelta| {
+        date.checked_add_signed(duration).ok_or_else(out_of_range)
+    };
+    let duration = Some((days * 86400.0).round())
+        .filter(|seconds| seconds.is_finite() && seconds.abs() < i64::MAX as f64)
+        .and_then(|seconds| TimeDelta::try_seconds(seconds as i64))
+        .ok_or_else(out_of_range)?;
+    match value {
+        Cell::DateTime(date_time) => date_time
+            .checked_add_signed(duration)
+            .map(Cell::DateTime)
+            .ok_or_else(out_of_range),
+        Cell::DateRange(start, end) => Ok(Cell::DateRange(
+            add(*start, duration)?,
+            add(*end, duration)?,
+        )),
+        _ => Ok(Cell::Date(add(to_date(value)?, duration)?)),
+    }
+}
+
+pub fn compare(lhs: &Cell, rhs: &Cell) -> Option<Ordering> {
+    match (lhs, rhs) {
+        (Cell::Empty, Cell::Empty) => Some(Ordering::Equal),
+        (Cell::Empty, _) | (_, Cell::Empty) => None,
+        (Cell::Number(a, _), Cell::Number(b, _)) => a.partial_cmp(b),
+        (Cell::Boolean(a), Cell::Boolean(b)) => Some(a.cmp(b)),
+        (Cell::DateTime(a), Cell::DateTime(b)) => Some(a.cmp(b)),
+        (a, b) if is_date(a) && is_date(b) => Some(to_date(a).ok()?.cmp(&to_date(b).ok()?)),
+        (a, b) if !is_date(a) && !is_date(b) => Some(to_text(a).cmp(&to_text(b))),
+        _ => None,
+    }
+}
+
+fn binary(op: &str, lhs: Cell, rhs: Cell) -> Result<Cell, String> {
+    match op {
+        "+" if is_date(&lhs) => add_days(&lhs, to_number(&rhs)?),
+        "+" if is_date(&rhs) => add_days(&rhs, to_number(&lhs)?),
+        "+" if matches!(lhs, Cell::Text(_)) || matches!(rhs, Cell::Text(_)) => {
+            Ok(Cell::Text(to_text(&lhs) + &to_text(&rhs)))
+        }
+        "+" => Ok(Cell::number(to_number(&lhs)? + to_number(&rhs)?)),
+        "-" if is_date(&lhs) && is_date(&rhs) => Ok(Cell::number(
+            (to_date(&lhs)? - to_date(&rhs)?).num_days() as f64,
+        )),
+        "-" if is_date(&lhs) => add_days(&lhs, -to_number(&rhs)?),
+        "-" => Ok(Cell::number(to_number(&lhs)? - to_number(&rhs)?)),
+        "*" => Ok(Cell::number(to_number(&lhs)? * to_number(&rhs)?)),
+        "/" | "%" if to_number(&rhs)? == 0.0 => Err("division by zero".to_string()),
+        "/" => Ok(Cell::number(to_number(&lhs)? / to_number(&rhs)?)),
+        "%" => Ok(Cell::number(to_number(&lhs)? % to_number(&rhs)?)),
+        "==" => Ok(Cell::Boolean(compare(&lhs, &rhs) == Some(Ordering::Equal))),
+        "!=" => Ok(Cell::Boolean(compare(&lhs, &rhs) != Some(Ordering::Equal))),
+        "<" | "<=" | ">" | ">=" => match compare(&lhs, &rhs) {
+            Some(ordering) => Ok(Cell::Boolean(match op {
+                "<" => ordering.is_lt(),
+                "<=" => ordering.is_le(),
+                ">" => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            })),
+            // empty cells aren't before or after anything
+            None if lhs == Cell::Empty || rhs == Cell::Empty => Ok(Cell::Boolean(false)),
+            None => Err(format!("can't compare '{}' and '{}'", lhs, rhs)),
+        },
+        _ => Err(format!("unknown operator '{}'", op)),
+    }
+}
+
+fn expect_args(name: &str, args: &[Cell], count: usize) -> Result<(), String> {
+    if args.len() == count {
+        Ok(())
+    } else {
+        Err(format!("{}() takes {} argument(s)", name, count))
+    }
+}
+
+fn call(name: &str, args: Vec<Cell>) -> Result<Cell, String> {
+    let today: NaiveDate = Local::now().date_naive();
+    match name {
+        "today" => {
+            expect_args(name, &args, 0)?;
+            Ok(Cell::Date(today))
+        }
+        "now" => {
+            expect_args(name, &args, 0)?;
+            Ok(Cell::DateTime(Local::now().fixed_offset()))
+        }
+        "not" => {
+            expect_args(name, &args, 1)?;
+            Ok(Cell::Boolean(!is_truthy(&args[0])))
+        }
+        "empty" => {
+            expect_args(name, &args, 1)?;
+            Ok(Cell::Boolean(args[0] == Cell::Empty))
+        }
+        "concat" => Ok(Cell::Text(args.iter().map(to_text).collect())),
+        "length" => {
+            expect_args(name, &args, 1)?;
+            Ok(Cell::number(to_text(&args[0]).chars().count() as f64))
+        }
+        "upper" | "lower" | "trim" => {
+            expect_args(name, &args, 1)?;
+            let text = to_text(&args[0]);
+            Ok(Cell::Text(match name {
+                "upper" => text.to_uppercase(),
+                "lower" => text.to_lowercase(),
+                _ => text.trim().to_string(),
+            }))
+        }
+        "contains" => {
+            expect_args(name, &args, 2)?;
+            Ok(Cell::Boolean(match &args[0] {
+                Cell::Options(options) => options.contains(&to_text(&args[1])),
+                value => to_text(value).contains(&to_text(&args[1])),
+            }))
+        }
+        "replace" => {
+            expect_args(name, &args, 3)?;
+            Ok(Cell::Text(
+                to_text(&args[0]).replace(&to_text(&args[1]), &to_text(&args[2])),
+            ))
+        }
+        "text" => {
+            expect_args(name, &args, 1)?;
+            Ok(Cell::Text(to_text(&args[0])))
+        }
+        "number" => {
+            expect_args(name, &args, 1)?;
+            Ok(Cell::number(to_number(&args[0])?))
+        }
+        "abs" | "floor" | "ceil" => {
+            expect_args(name, &args, 1)?;
+            let num = to_number(&args[0])?;
+            Ok(Cell::number(match name {
+                "abs" => num.abs(),
+                "floor" => num.floor(),
+                _ => num.ceil(),
+            }))
+        }
+        "round" => {
+            let places = match args.len() {
+                1 => 0.0,
+                2 => to_number(&args[1])?,
+                _ => return Err("round() takes 1 or 2 arguments".to_string()),
+            };
+            let scale = 10f64.powf(places);
+            Ok(Cell::number((to_number(&args[0])? * scale).round() / scale))
+        }
+        "min" | "max" if args.is_empty() => Err(format!("{}() needs an argument", name)),
+        "min" | "max" => {
+            let nums = args
+                .iter()
+                .map(to_number)
+                .collect::<Result<Vec<f64>, String>>()?;
+            let pick = if name == "min" { f64::min } else { f64::max };
+            Ok(Cell::number(nums.into_iter().reduce(pick).unwrap()))
+        }
+        "days_until" => {
+            expect_args(name, &args, 1)?;
+            Ok(Cell::number((to_date(&args[0])? - today).num_days() as f64))
+        }
+        "days_since" => {
+            expect_args(name, &args, 1)?;
+            Ok(Cell::number((today - to_date(&args[0])?).num_days() as f64))
+        }
+        "days_between" => {
+            expect_args(name, &args, 2)?;
+            Ok(Cell::number(
+                (to_date(&args[1])? - to_date(&args[0])?).num_days() as f64,
+            ))
+        }
+        "add_days" => {
+            expect_args(name, &args, 2)?;
+            add_days(&args[0], to_number(&args[1])?)
+        }
+        "year" | "month" | "day" | "weekday" => {
+            expect_args(name, &args, 1)?;
+            let date = to_date(&args[0])?;
+            Ok(Cell::number(match name {
+                "year" => date.year(),
+                "month" => date.month() as i32,
+                "day" => date.day() as i32,
+                _ => date.weekday().number_from_monday() as i32,
+            } as f64))
+        }
+        "start" | "end" => {
+            expect_args(name, &args, 1)?;
+            match args[0] {
+                Cell::DateRange(start, end) => {
+                    Ok(Cell::Date(if name == "start" { start } else { end }))
+                }
+                _ => Ok(Cell::Date(to_date(&args[0])?)),
+            }
+        }
+        "format_date" => {
+            expect_args(name, &args, 2)?;
+            let format = to_text(&args[1]);
+            if !crate::is_valid_date_format(&format) {
+                return Err(format!("bad date format '{}'", format));
+            }
+            Ok(Cell::Text(to_date(&args[0])?.format(&format).to_string()))
+        }
+        _ => Err(format!("unknown function '{}'", name)),
+    }
+}
+
+/// The names of the columns `expr` reads.
+pub fn columns(expr: &Expr) -> Vec<String> {
+    match expr {
+        Expr::Literal(_) => vec![],
+        Expr::Column(name) => vec![name.clone()],
+        Expr::Call(name, args) if name == "prop" => match args.as_slice() {
+            [Expr::Literal(Cell::Text(column))] => vec![column.clone()],
+            _ => args.iter().flat_map(columns).collect(),
+        },
+        Expr::Not(inner) | Expr::Neg(inner) => columns(inner),
+        Expr::Binary(_, lhs, rhs) => [columns(lhs), columns(rhs)].concat(),
+        Expr::Call(_, args) => args.iter().flat_map(columns).collect(),
+    }
+}
+
+/// `src` with its uses of the column `old` renamed to `new`, written the way `new` needs to be
+/// written. Formulas that don't tokenize are returned as they are.
+pub fn rename_column(src: &str, old: &str, new: &str) -> String {
+    let Ok(tokens) = spanned_tokens(src) else {
+        return src.to_string();
+    };
+    let is_keyword = |word: &str| {
+        ["true", "false", "today", "and", "or", "not"].contains(&word.to_lowercase().as_str())
+    };
+    let is_word = new
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && new.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !is_keyword(new);
+    let quoted = format!("\"{}\"", new.replace('\\', "\\\\").replace('"', "\\\""));
+    let bracketed = if new.contains(']') {
+        format!("prop({})", quoted)
+    } else {
+        format!("[{}]", new)
+    };
+    let token = |n: usize| tokens.get(n).map(|(token, _)| token);
+
+    let chars: Vec<char> = src.chars().collect();
+    let mut renamed = String::new();
+    let mut copied = 0;
+    for (n, (tok, span)) in tokens.iter().enumerate() {
+        let replacement = match tok {
+            Token::Column(name) if name == old => &bracketed,
+            Token::Ident(name)
+                if name == old && token(n + 1) != Some(&Token::LParen) && !is_keyword(name) =>
+            {
+                if is_word {
+                    new
+                } else {
+                    &bracketed
+                }
+            }
+            Token::Str(name)
+                if name == old
+                    && n >= 2
+                    && matches!(token(n - 2), Some(Token::Ident(call)) if call.eq_ignore_ascii_case("prop"))
+                    && token(n - 1) == Some(&Token::LParen)
+                    && token(n + 1) == Some(&Token::RParen) =>
+            {
+                &quoted
+            }
+            _ => continue,
+        };
+        renamed.extend(&chars[copied..span.start]);
+        renamed.push_str(replacement);
+        copied = span.end;
+    }
+    renamed.extend(&chars[copied..]);
+    renamed
+}
+
+/// Evaluates `expr`, reading the cells of its row with `lookup`.
+pub fn eval(expr: &Expr, lookup: &dyn Fn(&str) -> Option<Cell>) -> Result<Cell, String> {
+    match expr {
+        Expr::Literal(value) => Ok(value.clone()),
+        Expr::Column(name) => match lookup(name) {
+            Some(Cell::Invalid(raw)) => Err(format!("'{}' has an invalid value '{}'", name, raw)),
+            Some(value) => Ok(value),
+            None => Err(format!("there is no column '{}'", name)),
+        },
+        Expr::Not(inner) => Ok(Cell::Boolean(!is_truthy(&eval(inner, lookup)?))),
+        Expr::Neg(inner) => Ok(Cell::number(-to_number(&eval(inner, lookup)?)?)),
+        // only evaluates the side that is needed
+        Expr::Binary("&&", lhs, rhs) => Ok(Cell::Boolean(
+            is_truthy(&eval(lhs, lookup)?) && is_truthy(&eval(rhs, lookup)?),
+        )),
+        Expr::Binary("||", lhs, rhs) => Ok(Cell::Boolean(
+            is_truthy(&eval(lhs, lookup)?) || is_truthy(&eval(rhs, lookup)?),
+        )),
+        Expr::Binary(op, lhs, rhs) => binary(op, eval(lhs, lookup)?, eval(rhs, lookup)?),
+        Expr::Call(name, args) if name == "if" => {
+            if args.len() != 3 {
+                return Err("if() takes 3 arguments".to_string());
+            }
+            if is_truthy(&eval(&args[0], lookup)?) {
+                eval(&args[1], lookup)
+            } else {
+                eval(&args[2], lookup)
+            }
+        }
+        Expr::Call(name, args) if name == "prop" => match args.as_slice() {
+            [Expr::Literal(Cell::Text(column))] => eval(&Expr::Column(column.clone()), lookup),
+            _ => Err("prop() takes the name of a column in quotes".to_string()),
+        },
+        Expr::Call(name, args) => {
+            let args = args
+                .iter()
+                .map(|arg| eval(arg, lookup))
+                .collect::<Result<Vec<Cell>, String>>()?;
+            call(name, args)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evaluates `src` in a row where `Date` is 06/23/2023, `Count` is 3 and `Done` is checked.
+    fn run(src: &str) -> Result<Cell, String> {
+        let lookup = |name: &str| match name {
+            "Date" => Some(Cell::Date(NaiveDate::from_ymd_opt(2023, 6, 23).unwrap())),
+            "Count" => Some(Cell::number(3.0)),
+            "Done" => Some(Cell::Boolean(true)),
+            "Due Date" => Some(Cell::Empty),
+            _ => None,
+        };
+        eval(&parse(src)?, &lookup)
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> Cell {
+        Cell::Date(NaiveDate::from_ymd_opt(year, month, day).unwrap())
+    }
+
+    #[test]
+    fn tokenizes_durations_and_columns() {
+        assert_eq!(
+            tokenize("7d + 2w - [Due Date]").unwrap(),
+            vec![
+                Token::Number(7.0),
+                Token::Op("+"),
+                Token::Number(14.0),
+                Token::Op("-"),
+                Token::Column("Due Date".to_string()),
+            ]
+        );
+        // a unit only counts at the end of the number
+        assert_eq!(
+            tokenize("7days").unwrap(),
+            vec![Token::Number(7.0), Token::Ident("days".to_string())]
+        );
+        assert!(tokenize("\"open").is_err());
+        assert!(tokenize("[open").is_err());
+        assert!(tokenize("1 $ 2").is_err());
+    }
+
+    #[test]
+    fn renames_columns_where_they_are_used() {
+        assert_eq!(
+            rename_column("Count * 2 + count(x)", "Count", "Total"),
+            "Total * 2 + count(x)"
+        );
+        assert_eq!(
+            rename_column(
+                "[Due Date] < today and prop(\"Due Date\")",
+                "Due Date",
+                "Due"
+            ),
+            "[Due] < today and prop(\"Due\")"
+        );
+        assert_eq!(
+            rename_column("Count + 1", "Count", "Item Count"),
+            "[Item Count] + 1"
+        );
+        assert_eq!(rename_column("Count", "Count", "a]b"), "prop(\"a]b\")");
+        assert_eq!(
+            rename_column("Count + \"Count\"", "Count", "Total"),
+            "Total + \"Count\""
+        );
+        assert_eq!(rename_column("Count + \"", "Count", "Total"), "Count + \"");
+    }
+
+    #[test]
+    fn parses_with_precedence() {
+        assert_eq!(run("1 + 2 * 3"), Ok(Cell::number(7.0)));
+        assert_eq!(run("(1 + 2) * 3"), Ok(Cell::number(9.0)));
+        assert_eq!(run("-2 * -3"), Ok(Cell::number(6.0)));
+        assert_eq!(run("not 1 > 2 and true"), Ok(Cell::Boolean(true)));
+        assert_eq!(run("false or Count == 3"), Ok(Cell::Boolean(true)));
+        assert!(parse("1 +").is_err());
+        assert!(parse("(1").is_err());
+        assert!(parse("1 2").is_err());
+    }
+
+    #[test]
+    fn evaluates_cells_and_functions() {
+        assert_eq!(
+            run("if(Done, \"done\", Count)"),
+            Ok(Cell::Text("done".to_string()))
+        );
+        assert_eq!(run("prop(\"Count\") + 1"), Ok(Cell::number(4.0)));
+        assert_eq!(run("empty([Due Date])"), Ok(Cell::Boolean(true)));
+        assert_eq!(run("round(10 / 3, 2)"), Ok(Cell::number(3.33)));
+        assert_eq!(run("concat(\"a\", 1)"), Ok(Cell::Text("a1".to_string())));
+        assert_eq!(run("month(Date)"), Ok(Cell::number(6.0)));
+        assert!(run("1 / 0").is_err());
+        assert!(run("Missing").is_err());
+        assert!(run("nope(1)").is_err());
+        assert_eq!(
+            columns(&parse("Count + prop(\"Date\") + [Due Date]").unwrap()),
+            vec!["Count", "Date", "Due Date"]
+        );
+    }
+
+    #[test]
+    fn adds_days_and_durations_to_dates() {
+        assert_eq!(run("Date + 7d"), Ok(date(2023, 6, 30)));
+        assert_eq!(run("Date - 1w"), Ok(date(2023, 6, 16)));
+        assert_eq!(run("2 + Date"), Ok(date(2023, 6, 25)));
+        assert_eq!(run("(Date + 10) - Date"), Ok(Cell::number(10.0)));
+        assert_eq!(run("Date < Date + 1"), Ok(Cell::Boolean(true)));
+    }
+
+    #[test]
+    fn out_of_range_dates_are_errors() {
+        let out_of_range =
+            |days: &str| Err(format!("{} days from 06/23/2023 is out of range", days));
+        assert_eq!(run("Date + 1000000000"), out_of_range("1000000000"));
+        assert_eq!(run("Date + 100000000d"), out_of_range("100000000"));
+        assert_eq!(run("Date - 100000000d"), out_of_range("-100000000"));
+        // too many seconds for a duration
+        assert_eq!(
+            run("add_days(Date, 1000000000000000)"),
+            out_of_range("1000000000000000")
+        );
+        assert!(run("add_days(now(), 1000000000000000)")
+            .is_err_and(|error| error.ends_with("is out of range")));
+        // too big for a float
+        let huge = format!("1{}", "0".repeat(400));
+        assert_eq!(
+            run(&format!("add_days(Date, {})", huge)),
+            out_of_range("inf")
+        );
+    }
+}
@@ -1,14 +1,25 @@
+use chrono::format::{Item, StrftimeItems};
 use chrono::prelude::*;
-use chrono::NaiveDate;
+use chrono::{Days, Months, NaiveDate};
 use iota::iota;
 use ncurses::*;
 use regex::Regex;
-use serde::{Deserialize, Serialize};
-use serde_json::Result;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Map, Result, Value};
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
 use std::io::Write;
+use std::ops::{Index, IndexMut};
 use std::str::FromStr;
-use std::{cmp::max, fs};
+use std::{
+    cmp::{max, min, Ordering},
+    fs,
+};
+
+mod formula;
+mod markdown;
 
 type ColorPair = i16;
 
@@ -29,374 +40,2596 @@ iota! {
     const INV_CYAN_PAIR: ColorPair = iota;
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 enum InputMode {
     Normal,
     Text,
     Cmd,
+    Search,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
-struct Date {
-    day: i32,
-    month: i32,
-    year: i32,
-}
-
-#[derive(Serialize, Deserialize, strum_macros::Display, Clone, strum_macros::EnumString)]
+#[derive(
+    Serialize,
+    Deserialize,
+    strum_macros::Display,
+    Clone,
+    strum_macros::EnumString,
+    Default,
+    PartialEq,
+)]
 #[serde(rename_all = "lowercase")]
 enum ColumnType {
     Date,
+    #[default]
     String,
     Boolean,
     Multiselect,
+    Select,
     Number,
+    DateRange,
+    Relation,
+    Rollup,
+    Formula,
+    Url,
+    Email,
+    Phone,
+    CreatedTime,
+    EditedTime,
+    LongText,
+    #[serde(untagged)]
+    Unknown(String),
 }
 
-fn str_as_col_type<'a>(str: &'a str, col_type: &'a ColumnType) -> (&'a str, ColorPair) {
-    let str_to_display: &str = match *col_type {
-        ColumnType::Boolean => {
-            if str == "T" || str == "t" {
-                "[X]"
-            } else {
-                "[ ]"
-            }
-        }
-        ColumnType::Number => match str.parse::<i32>() {
-            Ok(_) => str,
-            Err(_) => {
-                if str.is_empty() {
-                    ""
-                } else {
-                    "?"
-                }
-            }
-        },
-        ColumnType::Date => {
-            let date_regex: regex::Regex = Regex::new(r"^\d{2}/\d{2}/\d{4}$").unwrap();
-            if date_regex.is_match(str) {
-                str
-            } else {
-                "?"
-            }
-        }
-        _ => str,
-    };
-    let color_to_display: i16 = match *col_type {
-        ColumnType::Date => {
-            let today: NaiveDate = Local::now().naive_local().date();
-            match NaiveDate::parse_from_str(str, "%m/%d/%Y") {
-                Ok(_) if str.len() != 10 => BLUE_PAIR,
-                Ok(date) => {
-                    if date < today {
-                        RED_PAIR
-                    } else if date == today {
-                        WHITE_PAIR
-                    } else {
-                        GREEN_PAIR
-                    }
-                }
-                Err(_) => BLUE_PAIR,
-            }
-        }
-        ColumnType::Number => match str.parse::<i32>() {
-            Ok(_) => WHITE_PAIR,
-            Err(_) => {
-                if str.is_empty() {
-                    WHITE_PAIR
-                } else {
-                    BLUE_PAIR
-                }
-            }
-        },
-        _ => WHITE_PAIR,
-    };
+const DATE_FORMAT: &str = "%m/%d/%Y";
+const TIME_FORMAT: &str = "%H:%M";
+const DATE_TIME_FORMAT: &str = "%m/%d/%Y %H:%M:%S %:z";
+/// How date-times were stored before they kept their seconds.
+const OLD_DATE_TIME_FORMAT: &str = "%m/%d/%Y %H:%M %:z";
+
+/// Other ways a date can be typed in, tried after the column's own format.
+const INPUT_DATE_FORMATS: [&str; 7] = [
+    DATE_FORMAT,
+    "%Y-%m-%d",
+    "%Y/%m/%d",
+    "%d.%m.%Y",
+    "%B %d, %Y",
+    "%B %d %Y",
+    "%d %B %Y",
+];
 
-    (str_to_display, color_to_display)
+#[derive(
+    Serialize, Deserialize, strum_macros::Display, strum_macros::EnumString, Clone, PartialEq,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+enum OptionColor {
+    White,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    #[serde(untagged)]
+    Unknown(String),
 }
 
-fn column_symbols(col_type: &ColumnType) -> &str {
-    match col_type {
-        ColumnType::Date => "@",
-        ColumnType::String => "_",
-        ColumnType::Boolean => "?",
-        ColumnType::Number => "#",
-        ColumnType::Multiselect => "=",
-        // _ => "!",
+const OPTION_COLORS: [OptionColor; 7] = [
+    OptionColor::White,
+    OptionColor::Red,
+    OptionColor::Green,
+    OptionColor::Yellow,
+    OptionColor::Blue,
+    OptionColor::Magenta,
+    OptionColor::Cyan,
+];
+
+impl OptionColor {
+    fn pair(&self) -> ColorPair {
+        match self {
+            OptionColor::White => WHITE_PAIR,
+            OptionColor::Red => RED_PAIR,
+            OptionColor::Green => GREEN_PAIR,
+            OptionColor::Yellow => YELLOW_PAIR,
+            OptionColor::Blue => BLUE_PAIR,
+            OptionColor::Magenta => MAGENTA_PAIR,
+            OptionColor::Cyan => CYAN_PAIR,
+            OptionColor::Unknown(_) => WHITE_PAIR,
+        }
     }
 }
 
-#[derive(Serialize, Deserialize)]
-struct Column {
-    name: String,
-    width: i32,
-    column_type: ColumnType,
-    // default: value,
+#[derive(
+    Serialize, Deserialize, strum_macros::Display, strum_macros::EnumString, Clone, PartialEq,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+enum RollupFunction {
+    Count,
+    Sum,
+    Average,
+    Min,
+    Max,
+    PercentChecked,
+    Earliest,
+    Latest,
+    #[serde(untagged)]
+    Unknown(String),
 }
 
-fn label(text: &str, y: i32, x: i32, pair: i16) {
-    mv(y, x);
-    attron(COLOR_PAIR(pair));
-    addstr(text);
-    attroff(COLOR_PAIR(pair));
+/// A rollup computes `function` over `column` of the rows linked by its table's `relation` column.
+#[derive(Serialize, Deserialize, Clone)]
+struct Rollup {
+    relation: String,
+    column: String,
+    function: RollupFunction,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
 }
 
-#[derive(Serialize, Deserialize, strum_macros::Display)]
-enum TableFocus {
-    Table,
-    Element,
-    NewElement,
-    View,
-    Sort,
-    Column,
-    NewColumn,
-}
+impl Rollup {
+    fn compute(&self, values: &[Cell]) -> Cell {
+        let nums: Vec<f64> = values
+            .iter()
+            .filter_map(|value| match value {
+                Cell::Number(num, _) => Some(*num),
+                _ => None,
+            })
+            .collect();
+        let dates = values.iter().filter_map(|value| match value {
+            Cell::Date(date) => Some((*date, *date)),
+            Cell::DateTime(date_time) => {
+                let date = date_time.with_timezone(&Local).date_naive();
+                Some((date, date))
+            }
+            Cell::DateRange(start, end) => Some((*start, *end)),
+            _ => None,
+        });
 
-#[derive(Serialize, Deserialize)]
-struct Table {
-    title: String,
-    subtitle: String,
-    // views: Vec<View>,
-    // sorts: Vec<Sort>,
-    columns: Vec<Column>,
-    data: Vec<Vec<String>>,
-    curr_row: usize,
-    curr_col: usize,
-    num_mode: NumMode,
-    table_focus: TableFocus,
-    path: String,
-}
+        let result: Option<Cell> = match self.function {
+            RollupFunction::Count => Some(Cell::number(values.len() as f64)),
+            RollupFunction::Sum => Some(Cell::number(nums.iter().sum())),
+            RollupFunction::Average if nums.is_empty() => None,
+            RollupFunction::Average => {
+                Some(Cell::number(nums.iter().sum::<f64>() / nums.len() as f64))
+            }
+            RollupFunction::Min => nums.into_iter().reduce(f64::min).map(Cell::number),
+            RollupFunction::Max => nums.into_iter().reduce(f64::max).map(Cell::number),
+            RollupFunction::PercentChecked if values.is_empty() => None,
+            RollupFunction::PercentChecked => {
+                let checked = values
+                    .iter()
+                    .filter(|value| **value == Cell::Boolean(true))
+                    .count();
+                Some(Cell::number(checked as f64 / values.len() as f64))
+            }
+            RollupFunction::Earliest => dates.map(|(start, _)| start).min().map(Cell::Date),
+            RollupFunction::Latest => dates.map(|(_, end)| end).max().map(Cell::Date),
+            RollupFunction::Unknown(_) => None,
+        };
 
-impl Table {
-    fn draw_title(&self) {
-        label(&self.title, 0, 0, WHITE_PAIR);
+        result.unwrap_or(Cell::Empty)
     }
+}
 
-    fn draw_subtitle(&self) {
-        label(&self.subtitle, 2, 4, WHITE_PAIR);
-    }
+#[derive(Serialize, Deserialize, Clone)]
+struct SelectOption {
+    name: String,
+    color: OptionColor,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
 
-    fn draw_views(&self) {
-        label("View: ", 3, 4, WHITE_PAIR);
-        attron(COLOR_PAIR(INV_WHITE_PAIR));
-        addstr("All");
-        attroff(COLOR_PAIR(INV_WHITE_PAIR));
-    }
+const CURRENCY_SYMBOLS: [&str; 5] = ["$", "€", "£", "¥", "₹"];
 
-    fn draw_headers(&self) {
-        let num_col_size: usize = (self.data.len() as f32).log10() as usize + 1;
-        {
-            label("+", 4, 4, WHITE_PAIR);
-            addstr(&n_of_c(num_col_size + 2, '-'));
-            addstr("+");
+/// How a number column shows its values, written like a spreadsheet format: `$#,##0.00` is
+/// currency with thousands separators and two decimals, `0` is an integer and `0.0%` a percent.
+/// An empty format shows numbers as they were entered.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+struct NumberFormat {
+    currency: String,
+    thousands: bool,
+    decimals: Option<usize>,
+    percent: bool,
+}
 
-            for col in self.columns.iter() {
-                addstr(&format!("{}+", n_of_c((col.width + 2) as usize, '-')));
-            }
+impl FromStr for NumberFormat {
+    type Err = String;
+
+    fn from_str(spec: &str) -> std::result::Result<NumberFormat, String> {
+        let mut format = NumberFormat::default();
+        if spec.is_empty() {
+            return Ok(format);
         }
-        {
-            label(
-                &format!("| {} ", n_of_c(num_col_size, ' ')),
-                5,
-                4,
-                WHITE_PAIR,
-            );
 
-            for (col_num, col) in self.columns.iter().enumerate() {
-                let pair = match self.table_focus {
-                    TableFocus::Column => {
-                        if col_num == self.curr_col {
-                            INV_WHITE_PAIR
-                        } else {
-                            WHITE_PAIR
-                        }
-                    }
-                    _ => WHITE_PAIR,
-                };
-                addstr("| ");
-                attron(COLOR_PAIR(pair));
-                addstr(&format!("{}", col.name));
-                addstr(&format!("{}", column_symbols(&col.column_type)));
-                attroff(COLOR_PAIR(pair));
-                addstr(&n_of_c(col.width as usize - col.name.len() - 1, ' '));
-                addstr(" ");
-            }
-            addstr("|");
+        let body_start = spec
+            .find(['#', '0'])
+            .ok_or(format!("Error: Bad number format '{}'", spec))?;
+        format.currency = spec[..body_start].to_string();
+        let mut body = &spec[body_start..];
+        if let Some(stripped) = body.strip_suffix('%') {
+            format.percent = true;
+            body = stripped;
         }
-        {
-            // TODO is duplicate of 1st block
-            label("+", 6, 4, WHITE_PAIR);
-            addstr(&n_of_c(num_col_size + 2, '='));
-            addstr("+");
+        if body.chars().any(|c| !matches!(c, '#' | '0' | ',' | '.')) {
+            return Err(format!("Error: Bad number format '{}'", spec));
+        }
+        format.thousands = body.contains(',');
+        format.decimals = Some(match body.split_once('.') {
+            Some((_, decimals)) => decimals.len(),
+            None => 0,
+        });
 
-            for col in self.columns.iter() {
-                addstr(&format!("{}+", n_of_c((col.width + 2) as usize, '=')));
-            }
+        Ok(format)
+    }
+}
+
+impl fmt::Display for NumberFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let decimals = match self.decimals {
+            Some(decimals) => decimals,
+            None => return Ok(()),
+        };
+        write!(f, "{}", self.currency)?;
+        write!(f, "{}", if self.thousands { "#,##0" } else { "0" })?;
+        if decimals > 0 {
+            write!(f, ".{}", n_of_c(decimals, '0'))?;
+        }
+        if self.percent {
+            write!(f, "%")?;
         }
+
+        Ok(())
     }
+}
 
-    fn draw_data(&self) {
-        let start_y: i32 = 7;
-        let num_col_size: usize = (self.data.len() as f32).log10() as usize + 1;
-        for (row_num, row) in self.data.iter().enumerate() {
-            // TODO freak out if row longer than columns?
+impl TryFrom<String> for NumberFormat {
+    type Error = String;
 
-            let pair: i16 = if row_num == self.curr_row as usize {
-                INV_WHITE_PAIR
-            } else {
-                WHITE_PAIR
-            };
-            mv(row_num as i32 + start_y, 4);
-            attron(COLOR_PAIR(pair));
-            {
-                addstr("| ");
-                addstr(&fit_to_sizer(
-                    &format!(
-                        "{} ",
-                        match self.num_mode {
-                            NumMode::Absolute => row_num + 1,
-                            NumMode::Relative =>
-                                (row_num as i32 - self.curr_row as i32).abs() as usize,
-                        }
-                    ),
-                    num_col_size + 1,
-                    ' ',
-                ));
-            }
-            for (col_num, item) in row.iter().enumerate() {
-                let str_to_display: &str;
-                let mut color_to_display: i16;
-                (str_to_display, color_to_display) =
-                    str_as_col_type(item, &self.columns[col_num].column_type);
-                if self.curr_row == row_num {
-                    color_to_display += 1; // turns from normal to inverse
-                }
-                addstr("| ");
-                attron(COLOR_PAIR(color_to_display));
-                addstr(&fit_to_sizel(
-                    &format!("{}", str_to_display),
-                    self.columns[col_num].width as usize,
-                    ' ',
-                ));
-                attroff(COLOR_PAIR(color_to_display));
-                attron(COLOR_PAIR(pair));
-                addstr(" ");
-            }
-            addstr("|");
-            attroff(COLOR_PAIR(pair));
-        }
+    fn try_from(spec: String) -> std::result::Result<NumberFormat, String> {
+        NumberFormat::from_str(&spec)
     }
+}
 
-    fn draw_footer(&self) {
-        {
-            label("+", 7 + self.data.len() as i32, 4, WHITE_PAIR);
-            let num_col_size: usize = (self.data.len() as f32).log10() as usize + 1;
-            addstr(&n_of_c(num_col_size + 2, '-'));
-            addstr("+");
+impl From<NumberFormat> for String {
+    fn from(format: NumberFormat) -> String {
+        format.to_string()
+    }
+}
 
-            for col in self.columns.iter() {
-                addstr(&format!("{}+", n_of_c((col.width + 2) as usize, '-')));
-            }
-            label(
-                &format!(
-                    "{} {}",
-                    self.data.len(),
-                    if self.data.len() == 1 {
-                        "entry"
-                    } else {
-                        "entries"
-                    }
-                ),
-                8 + self.data.len() as i32,
-                5,
-                WHITE_PAIR,
-            );
-        }
+impl NumberFormat {
+    fn is_empty(&self) -> bool {
+        *self == NumberFormat::default()
     }
 
-    fn draw_elem(&self, motion_num: usize, input_mode: InputMode, input_str: &str) {
-        label(&format!("Row {}", self.curr_row + 1), 4, 4, WHITE_PAIR);
-        let start_y: usize = 6;
-        for (col_num, item) in self.data[self.curr_row].iter().enumerate() {
-            label(
-                &format!(
-                    "[{}|{}{}]",
-                    col_num + 1,
-                    self.columns[col_num].name,
-                    column_symbols(&self.columns[col_num].column_type)
-                ),
-                (start_y + col_num * 3) as i32,
-                4,
-                WHITE_PAIR,
-            );
-            let item_color: ColorPair;
-            (_, item_color) = str_as_col_type(item, &self.columns[col_num].column_type);
-            attron(COLOR_PAIR(item_color));
-            label(
-                &format!("{}", item),
-                (start_y + col_num * 3 + 1) as i32,
-                6,
-                WHITE_PAIR,
-            );
-            attroff(COLOR_PAIR(item_color));
-            match input_mode {
-                InputMode::Text => {
-                    if motion_num == col_num + 1 {
-                        addstr(&format!(" -> {}", input_str));
-                        attron(COLOR_PAIR(INV_WHITE_PAIR));
-                        addstr(" ");
-                        attroff(COLOR_PAIR(INV_WHITE_PAIR));
-                    }
+    fn format(&self, num: f64) -> String {
+        let value = if self.percent { num * 100.0 } else { num };
+        let digits = match self.decimals {
+            Some(decimals) => format!("{:.*}", decimals, value.abs()),
+            None => value.abs().to_string(),
+        };
+        // so -0.001 shown with two decimals isn't "-0.00"
+        let is_negative =
+            value < 0.0 && digits.contains(['1', '2', '3', '4', '5', '6', '7', '8', '9']);
+        let (int_part, frac_part) = match digits.split_once('.') {
+            Some((int_part, frac_part)) => (int_part.to_string(), format!(".{}", frac_part)),
+            None => (digits, "".to_string()),
+        };
+        let int_part = if self.thousands {
+            let mut grouped = "".to_string();
+            for (i, c) in int_part.chars().enumerate() {
+                if i > 0 && (int_part.len() - i) % 3 == 0 {
+                    grouped.push(',');
                 }
-                _ => {}
+                grouped.push(c);
             }
+            grouped
+        } else {
+            int_part
+        };
+
+        format!(
+            "{}{}{}{}{}",
+            if is_negative { "-" } else { "" },
+            self.currency,
+            int_part,
+            frac_part,
+            if self.percent { "%" } else { "" }
+        )
+    }
+}
+
+/// Whether `raw` looks like a value of a link or contact column, like `example.com`,
+/// `me@example.com` or `+1 (555) 123-4567`.
+fn is_valid_contact(raw: &str, col_type: &ColumnType) -> bool {
+    let pattern = match col_type {
+        ColumnType::Url => {
+            r"(?i)^(https?://\S+|[a-z0-9-]+(\.[a-z0-9-]+)*\.[a-z]{2,}(:\d+)?([/?#]\S*)?)$"
+        }
+        ColumnType::Email => r"^[^@\s]+@[^@\s]+\.[^@\s]+$",
+        ColumnType::Phone => r"^\+?[\d\s().-]+$",
+        _ => return false,
+    };
+    let digits = raw.chars().filter(|c| c.is_ascii_digit()).count();
+    Regex::new(pattern).unwrap().is_match(raw)
+        && (!matches!(col_type, ColumnType::Phone) || (3..=15).contains(&digits))
+}
+
+/// What the system opener is given for a link or contact cell. Links from table files can come
+/// from anyone, so only web links are opened, never `file://` or the like.
+fn open_target(cell: &Cell, col_type: &ColumnType) -> Option<String> {
+    let text = match cell {
+        Cell::Text(text) => text,
+        _ => return None,
+    };
+    match col_type {
+        ColumnType::Url if text.contains("://") => {
+            let scheme = text.split("://").next()?.to_lowercase();
+            matches!(scheme.as_str(), "http" | "https").then(|| text.clone())
         }
+        ColumnType::Url => Some(format!("https://{}", text)),
+        ColumnType::Email => Some(format!("mailto:{}", text)),
+        ColumnType::Phone => Some(format!(
+            "tel:{}",
+            text.chars()
+                .filter(|c| c.is_ascii_digit() || *c == '+')
+                .collect::<String>()
+        )),
+        _ => None,
     }
+}
 
-    fn draw_column(&self, motion_num: usize, input_mode: InputMode, input_str: &str) {
-        let start_y: usize = 8;
-        let col = &self.columns[self.curr_col];
-        label("[1|name]: ", start_y as i32, 8, WHITE_PAIR);
-        addstr(&format!("{}", col.name));
-        match input_mode {
-            InputMode::Text if motion_num == 1 => _ = addstr(&format!(" -> {}", input_str)),
-            _ => {}
+/// Reads numbers the way people write them: `1,234.5`, `-$12.50` or `15%` (read as 0.15).
+/// The digits of `raw` when it's a plain decimal like `12345678901234567891` that has more of them
+/// than `num` keeps, so that they're saved the way they were written.
+fn exact_digits(raw: &str, num: f64) -> Option<String> {
+    let normalize = |text: &str| {
+        let (sign, digits) = match text.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", text),
         };
-        label("[2|width]:  ", start_y as i32 + 1, 8, WHITE_PAIR);
-        addstr(&format!("{}", col.width));
-        match input_mode {
-            InputMode::Text if motion_num == 2 => _ = addstr(&format!(" -> {}", input_str)),
-            _ => {}
+        let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+        if int_part.is_empty() && frac_part.is_empty()
+            || !int_part
+                .chars()
+                .chain(frac_part.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+        let int_part = match int_part.trim_start_matches('0') {
+            "" => "0",
+            int_part => int_part,
         };
-        label("[3|type]: ", start_y as i32 + 2, 8, WHITE_PAIR);
-        addstr(&format!("{}", col.column_type.to_string()));
-        match input_mode {
-            InputMode::Text if motion_num == 3 => _ = addstr(&format!(" -> {}", input_str)),
-            _ => {}
+        let normal = match frac_part.trim_end_matches('0') {
+            "" => format!("{}{}", sign, int_part),
+            frac_part => format!("{}{}.{}", sign, int_part, frac_part),
         };
-        // TODO #14 default value
-    }
+        Some(if normal == "-0" {
+            "0".to_string()
+        } else {
+            normal
+        })
+    };
+    let exact = normalize(raw.trim())?;
+    (normalize(&num.to_string()).as_ref() != Some(&exact)).then_some(exact)
+}
 
-    fn to_new_elem_mode(&mut self) {
-        self.data.push(vec!["".to_string(); self.columns.len()]);
-        self.table_focus = TableFocus::NewElement;
+fn parse_number(raw: &str) -> Option<f64> {
+    let mut text: String = raw.trim().replace(',', "");
+    let is_percent = text.ends_with('%');
+    if is_percent {
+        text.pop();
     }
-
-    fn to_view_mode(&mut self) {
-        self.table_focus = TableFocus::View;
+    let mut is_negative = text.starts_with('-');
+    if is_negative {
+        text.remove(0);
+    }
+    for symbol in CURRENCY_SYMBOLS {
+        if let Some(stripped) = text.strip_prefix(symbol) {
+            text = stripped.to_string();
+            break;
+        }
+    }
+    // both -$5 and $-5
+    if !is_negative && text.starts_with('-') {
+        is_negative = true;
+        text.remove(0);
     }
 
-    fn to_sort_mode(&mut self) {
-        self.table_focus = TableFocus::Sort;
+    let mut num: f64 = match text.parse::<f64>() {
+        Ok(num) if num.is_finite() && !text.starts_with(['+', '-']) => num,
+        _ => return None,
+    };
+    if is_negative {
+        num = -num;
+    }
+    if is_percent {
+        num /= 100.0;
     }
 
-    fn to_table_mode(&mut self) {
-        self.table_focus = TableFocus::Table;
+    Some(num)
+}
+
+/// Date formats can't have times in them, those are added when a date has a time.
+fn is_valid_date_format(format: &str) -> bool {
+    use std::fmt::Write;
+
+    !StrftimeItems::new(format).any(|item| item == Item::Error)
+        && write!(String::new(), "{}", NaiveDate::MIN.format(format)).is_ok()
+}
+
+fn parse_date(raw: &str, format: &str) -> Option<NaiveDate> {
+    let today: NaiveDate = Local::now().date_naive();
+    match raw.to_lowercase().as_str() {
+        "today" => return Some(today),
+        "tomorrow" => return today.succ_opt(),
+        "yesterday" => return today.pred_opt(),
+        _ => {}
     }
 
-    fn to_col_mode(&mut self) {
-        self.table_focus = TableFocus::Column;
-        self.curr_col = 0;
+    std::iter::once(format)
+        .chain(INPUT_DATE_FORMATS)
+        .filter_map(|format| NaiveDate::parse_from_str(raw, format).ok())
+        .find(|date| (1..=9999).contains(&date.year()))
+}
+
+fn parse_offset(raw: &str) -> Option<FixedOffset> {
+    let raw = raw.to_lowercase();
+    if raw == "z" || raw == "utc" {
+        return FixedOffset::east_opt(0);
+    }
+    let sign: i32 = if raw.starts_with('-') { -1 } else { 1 };
+    let digits: String = raw[1..].chars().filter(|c| *c != ':').collect();
+    let (hours, minutes) = match digits.len() {
+        2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (
+            digits[..2].parse::<i32>().ok()?,
+            digits[2..].parse::<i32>().ok()?,
+        ),
+        _ => return None,
+    };
+
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Reads a date with an optional time of day and timezone, like `2023-06-23`, `6/23/2023 2:30pm`
+/// or `2023-06-23T14:30 +02:00`. Times without a timezone are in local time.
+fn parse_date_cell(raw: &str, format: &str) -> Option<Cell> {
+    let raw = raw.trim();
+    if raw.eq_ignore_ascii_case("now") {
+        return Some(Cell::DateTime(Local::now().fixed_offset()));
+    }
+    let time_regex: regex::Regex = Regex::new(
+        r"(?i)(?:^|[T ])\s*(\d{1,2}):(\d{2})(?::(\d{2}))?\s*([ap]m)?(?:\s*(z|utc|[+-]\d{2}(?::?\d{2})?))?$",
+    )
+    .unwrap();
+    let caps = match time_regex.captures(raw) {
+        Some(caps) => caps,
+        None => return parse_date(raw, format).map(Cell::Date),
+    };
+
+    let date = parse_date(raw[..caps.get(0).unwrap().start()].trim(), format)?;
+    let mut hour: u32 = caps[1].parse().ok()?;
+    let minute: u32 = caps[2].parse().ok()?;
+    let second: u32 = caps
+        .get(3)
+        .map_or(Some(0), |sec| sec.as_str().parse().ok())?;
+    if let Some(am_pm) = caps.get(4) {
+        if hour == 0 || hour > 12 {
+            return None;
+        }
+        hour %= 12;
+        if am_pm.as_str().eq_ignore_ascii_case("pm") {
+            hour += 12;
+        }
+    }
+    let date_time = date.and_hms_opt(hour, minute, second)?;
+    let date_time = match caps.get(5) {
+        Some(offset) => parse_offset(offset.as_str())?
+            .from_local_datetime(&date_time)
+            .single()?,
+        None => Local
+            .from_local_datetime(&date_time)
+            .earliest()?
+            .fixed_offset(),
+    };
+
+    Some(Cell::DateTime(date_time))
+}
+
+const RANGE_SEPARATORS: [&str; 4] = [" - ", " to ", "..", "~"];
+
+/// Reads the two ends of a range like `6/1/2023 - 6/14/2023`, a single date is a one day range.
+fn parse_date_range(raw: &str, format: &str) -> Option<(NaiveDate, NaiveDate)> {
+    for separator in RANGE_SEPARATORS {
+        if let Some((start, end)) = raw.split_once(separator) {
+            return Some((
+                parse_date(start.trim(), format)?,
+                parse_date(end.trim(), format)?,
+            ));
+        }
+    }
+    let date = parse_date(raw.trim(), format)?;
+
+    Some((date, date))
+}
+
+fn date_color(date: NaiveDate) -> ColorPair {
+    let today: NaiveDate = Local::now().date_naive();
+    if date < today {
+        RED_PAIR
+    } else if date == today {
+        WHITE_PAIR
+    } else {
+        GREEN_PAIR
+    }
+}
+
+/// A single typed value in the table.
+///
+/// Values that don't fit their column's type (like "Hello World" in a number column) are kept as
+/// `Invalid` so that loading and saving a table never drops a value. Values that do fit are saved
+/// the way their type writes them, so `6/3/2023` comes back as `06/03/2023`, `T` as `t` and `007`
+/// as `7`.
+#[derive(Clone, Debug, PartialEq)]
+enum Cell {
+    Empty,
+    Text(String),
+    Boolean(bool),
+    /// The number, and the digits it was written with when there are more than an f64 keeps.
+    Number(f64, Option<String>),
+    Date(NaiveDate),
+    DateTime(DateTime<FixedOffset>),
+    DateRange(NaiveDate, NaiveDate),
+    Options(Vec<String>),
+    Select(String),
+    Relation(Vec<RowId>),
+    Invalid(String),
+}
+
+impl Cell {
+    fn number(num: f64) -> Cell {
+        Cell::Number(num, None)
+    }
+
+    fn parse(raw: &str, column: &Column) -> Cell {
+        if raw.is_empty() {
+            return Cell::Empty;
+        }
+
+        match column.column_type {
+            ColumnType::String => Cell::Text(raw.to_string()),
+            ColumnType::LongText if raw.trim().is_empty() => Cell::Empty,
+            ColumnType::LongText => Cell::Text(raw.trim_end().to_string()),
+            ColumnType::Multiselect => Cell::Options(
+                raw.split(',')
+                    .map(|option| option.trim().to_string())
+                    .filter(|option| !option.is_empty())
+                    .collect(),
+            ),
+            ColumnType::Select => Cell::Select(raw.trim().to_string()),
+            ColumnType::Url | ColumnType::Email | ColumnType::Phone => {
+                if is_valid_contact(raw.trim(), &column.column_type) {
+                    Cell::Text(raw.trim().to_string())
+                } else {
+                    Cell::Invalid(raw.to_string())
+                }
+            }
+            // computed from other cells, so nothing is stored
+            ColumnType::Rollup | ColumnType::Formula => Cell::Empty,
+            ColumnType::Unknown(_) => Cell::Text(raw.to_string()),
+            ColumnType::Relation => {
+                match raw
+                    .split(',')
+                    .map(|id| id.trim().parse::<RowId>())
+                    .collect()
+                {
+                    Ok(ids) => Cell::Relation(ids),
+                    Err(_) => Cell::Invalid(raw.to_string()),
+                }
+            }
+            ColumnType::Boolean => match raw {
+                "t" | "T" => Cell::Boolean(true),
+                "f" | "F" => Cell::Boolean(false),
+                _ => Cell::Invalid(raw.to_string()),
+            },
+            ColumnType::Number => match parse_number(raw) {
+                Some(num) => Cell::Number(num, exact_digits(raw, num)),
+                None => Cell::Invalid(raw.to_string()),
+            },
+            ColumnType::Date | ColumnType::CreatedTime | ColumnType::EditedTime => {
+                match parse_date_cell(raw, column.date_format()) {
+                    Some(cell) => cell,
+                    None => Cell::Invalid(raw.to_string()),
+                }
+            }
+            ColumnType::DateRange => match parse_date_range(raw, column.date_format()) {
+                Some((start, end)) if start <= end => Cell::DateRange(start, end),
+                _ => Cell::Invalid(raw.to_string()),
+            },
+        }
+    }
+
+    /// Reads a cell the way table files store it, which doesn't depend on the column's format.
+    /// Anything else is read like it was typed in.
+    fn parse_stored(raw: &str, column: &Column) -> Cell {
+        let date = |raw: &str| NaiveDate::parse_from_str(raw.trim(), DATE_FORMAT).ok();
+        let stored = match column.column_type {
+            ColumnType::Date | ColumnType::CreatedTime | ColumnType::EditedTime => {
+                [DATE_TIME_FORMAT, OLD_DATE_TIME_FORMAT]
+                    .iter()
+                    .find_map(|format| DateTime::parse_from_str(raw, format).ok())
+                    .map(Cell::DateTime)
+                    .or_else(|| date(raw).map(Cell::Date))
+            }
+            ColumnType::DateRange => raw
+                .split_once(" - ")
+                .and_then(|(start, end)| Some((date(start)?, date(end)?)))
+                .filter(|(start, end)| start <= end)
+                .map(|(start, end)| Cell::DateRange(start, end)),
+            _ => None,
+        };
+
+        stored.unwrap_or_else(|| Cell::parse(raw, column))
+    }
+
+    /// The first and last local days the value is on, when it's a date.
+    fn days(&self) -> Option<(NaiveDate, NaiveDate)> {
+        match self {
+            Cell::Date(date) => Some((*date, *date)),
+            Cell::DateTime(date_time) => {
+                let date = date_time.with_timezone(&Local).date_naive();
+                Some((date, date))
+            }
+            Cell::DateRange(start, end) => Some((*start, *end)),
+            _ => None,
+        }
+    }
+
+    /// Where the value goes when sorting either way, empty and invalid values go last.
+    fn sort_rank(&self) -> u8 {
+        match self {
+            Cell::Empty => 2,
+            Cell::Invalid(_) => 1,
+            _ => 0,
+        }
+    }
+
+    /// Where the kind of value goes when a column has values of many kinds, like a formula's, so
+    /// values are only compared with ones of their own kind.
+    fn kind_rank(&self) -> u8 {
+        match self {
+            Cell::Boolean(_) => 0,
+            Cell::Number(..) => 1,
+            Cell::Date(_) | Cell::DateTime(_) | Cell::DateRange(..) => 2,
+            Cell::Text(_) => 3,
+            Cell::Select(_) | Cell::Options(_) => 4,
+            Cell::Relation(_) => 5,
+            Cell::Invalid(_) => 6,
+            Cell::Empty => 7,
+        }
+    }
+
+    fn option_names(&self) -> &[String] {
+        match self {
+            Cell::Options(names) => names,
+            Cell::Select(name) => std::slice::from_ref(name),
+            _ => &[],
+        }
+    }
+
+    /// Like `Cell::parse_stored`, but keeps cells that already have the right type as they are.
+    fn typed(self, column: &Column) -> Cell {
+        match (&self, &column.column_type) {
+            (Cell::Options(_), ColumnType::Multiselect)
+            | (Cell::Select(_), ColumnType::Select)
+            | (
+                Cell::Date(_) | Cell::DateTime(_),
+                ColumnType::Date | ColumnType::CreatedTime | ColumnType::EditedTime,
+            )
+            | (Cell::DateRange(..), ColumnType::DateRange)
+            | (Cell::Relation(_), ColumnType::Relation)
+            | (_, ColumnType::Unknown(_)) => self,
+            _ => Cell::parse_stored(&self.to_string(), column),
+        }
+    }
+
+    /// What a cell becomes when its column changes to `column`'s type. Anything without a rule
+    /// goes through its stored text, which keeps what doesn't fit around as an invalid cell.
+    fn convert(self, column: &Column) -> Cell {
+        match (self, &column.column_type) {
+            (Cell::Empty, _) => Cell::Empty,
+            // the old values can't be kept in columns that are computed
+            (_, _) if column.is_computed() => Cell::Empty,
+            (Cell::Boolean(b), ColumnType::Number) => Cell::number(if b { 1.0 } else { 0.0 }),
+            (Cell::Number(num, _), ColumnType::Boolean) => Cell::Boolean(num != 0.0),
+            // a select only has room for the first
+            (Cell::Options(mut options), ColumnType::Select) if !options.is_empty() => {
+                Cell::Select(options.remove(0))
+            }
+            (Cell::Select(name), ColumnType::Multiselect) => Cell::Options(vec![name]),
+            (
+                Cell::DateRange(start, end),
+                ColumnType::Date | ColumnType::CreatedTime | ColumnType::EditedTime,
+            ) if start == end => Cell::Date(start),
+            (Cell::DateTime(date_time), ColumnType::DateRange) => {
+                let date = date_time.with_timezone(&Local).date_naive();
+                Cell::DateRange(date, date)
+            }
+            (cell, _) => cell.typed(column),
+        }
+    }
+}
+
+impl fmt::Display for Cell {
+    /// Writes the cell the way it is stored in table files.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Cell::Empty => Ok(()),
+            Cell::Text(text) | Cell::Select(text) | Cell::Invalid(text) => write!(f, "{}", text),
+            Cell::Boolean(true) => write!(f, "t"),
+            Cell::Boolean(false) => write!(f, "f"),
+            Cell::Number(_, Some(exact)) => write!(f, "{}", exact),
+            Cell::Number(num, None) => write!(f, "{}", num),
+            Cell::Date(date) => write!(f, "{}", date.format(DATE_FORMAT)),
+            Cell::DateTime(date_time) => write!(f, "{}", date_time.format(DATE_TIME_FORMAT)),
+            Cell::DateRange(start, end) => write!(
+                f,
+                "{} - {}",
+                start.format(DATE_FORMAT),
+                end.format(DATE_FORMAT)
+            ),
+            Cell::Options(options) => write!(f, "{}", options.join(", ")),
+            Cell::Relation(ids) => write!(
+                f,
+                "{}",
+                ids.iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+impl Serialize for Cell {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Cell::Options(options) => options.serialize(serializer),
+            Cell::Relation(ids) => ids.serialize(serializer),
+            _ => serializer.serialize_str(&self.to_string()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawCell {
+    Str(String),
+    List(Vec<String>),
+    Ids(Vec<RowId>),
+}
+
+impl<'de> Deserialize<'de> for Cell {
+    /// Cells are read as text, `Table::type_cells` gives them their column's type once the whole
+    /// table is loaded.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Cell, D::Error> {
+        Ok(match RawCell::deserialize(deserializer)? {
+            RawCell::Str(raw) if raw.is_empty() => Cell::Empty,
+            RawCell::Str(raw) => Cell::Text(raw),
+            RawCell::List(options) => Cell::Options(options),
+            RawCell::Ids(ids) => Cell::Relation(ids),
+        })
+    }
+}
+
+fn cell_as_col_type(cell: &Cell, column: &Column) -> (String, ColorPair) {
+    match cell {
+        Cell::Empty => match column.column_type {
+            ColumnType::Boolean => ("[ ]".to_string(), WHITE_PAIR),
+            _ => ("".to_string(), WHITE_PAIR),
+        },
+        Cell::Text(text) => match column.column_type {
+            ColumnType::Url | ColumnType::Email | ColumnType::Phone => (text.clone(), CYAN_PAIR),
+            // only the first line fits, the rest is in the row's page
+            ColumnType::LongText if text.contains('\n') => (
+                format!("{} ..", text.lines().next().unwrap_or("")),
+                WHITE_PAIR,
+            ),
+            _ => (text.clone(), WHITE_PAIR),
+        },
+        Cell::Boolean(true) => ("[X]".to_string(), WHITE_PAIR),
+        Cell::Boolean(false) => ("[ ]".to_string(), WHITE_PAIR),
+        Cell::Number(_, Some(exact)) if column.number_format().is_empty() => {
+            (exact.clone(), WHITE_PAIR)
+        }
+        Cell::Number(num, _) => (column.number_format().format(*num), WHITE_PAIR),
+        Cell::Date(date) => (
+            date.format(column.date_format()).to_string(),
+            date_color(*date),
+        ),
+        Cell::DateTime(date_time) => {
+            let local = date_time.with_timezone(&Local);
+            let mut text = date_time
+                .format(&format!("{} {}", column.date_format(), TIME_FORMAT))
+                .to_string();
+            // only shows the timezone when it isn't the local one
+            if local.offset().fix() != *date_time.offset() {
+                text.push_str(&date_time.format(" %:z").to_string());
+            }
+            (text, date_color(local.date_naive()))
+        }
+        Cell::DateRange(start, end) => {
+            let today: NaiveDate = Local::now().date_naive();
+            let color = if *end < today {
+                RED_PAIR
+            } else if *start <= today {
+                WHITE_PAIR
+            } else {
+                GREEN_PAIR
+            };
+            let text = format!(
+                "{} - {}",
+                start.format(column.date_format()),
+                end.format(column.date_format())
+            );
+            (text, color)
+        }
+        Cell::Options(options) => (options.join(" "), WHITE_PAIR),
+        Cell::Select(name) => (name.clone(), column.option_pair(name)),
+        Cell::Relation(ids) => (format!("{} linked", ids.len()), WHITE_PAIR),
+        Cell::Invalid(_) => ("?".to_string(), BLUE_PAIR),
+    }
+}
+
+/// Draws each option in its color, cut off at `width`. Returns how much of `width` was used.
+fn draw_tags(options: &[String], column: &Column, width: usize, inverse: bool) -> usize {
+    let mut used: usize = 0;
+    for (option_num, option) in options.iter().enumerate() {
+        let sep: usize = if option_num == 0 { 0 } else { 1 };
+        if used + sep >= width {
+            break;
+        }
+        addstr(&n_of_c(sep, ' '));
+        used += sep;
+
+        let mut pair = column.option_pair(option);
+        if inverse {
+            pair += 1; // turns from normal to inverse
+        }
+        let text = if used + option.len() <= width {
+            option.to_string()
+        } else {
+            fit_to_sizel(option, width - used, ' ')
+        };
+        attron(COLOR_PAIR(pair));
+        addstr(&text);
+        attroff(COLOR_PAIR(pair));
+        used += text.len();
+    }
+
+    used
+}
+
+fn column_symbols(col_type: &ColumnType) -> &str {
+    match col_type {
+        ColumnType::Date => "@",
+        ColumnType::String => "_",
+        ColumnType::Boolean => "?",
+        ColumnType::Number => "#",
+        ColumnType::Multiselect => "=",
+        ColumnType::Select => "*",
+        ColumnType::DateRange => "~",
+        ColumnType::Relation => "&",
+        ColumnType::Rollup => "^",
+        ColumnType::Formula => "$",
+        ColumnType::Url => "/",
+        ColumnType::Email => "<",
+        ColumnType::Phone => "+",
+        ColumnType::CreatedTime => ":",
+        ColumnType::EditedTime => ";",
+        ColumnType::LongText => "|",
+        ColumnType::Unknown(_) => "!",
+    }
+}
+
+/// Rules the values of a column have to follow when they are typed in.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+struct Constraints {
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    required: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    unique: bool,
+    /// Bounds for number and date columns, typed in like a value so dates can be like "today".
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    min: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    max: String,
+    /// A regex the whole text has to match.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pattern: String,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+impl Constraints {
+    fn is_empty(&self) -> bool {
+        *self == Constraints::default()
+    }
+}
+
+impl fmt::Display for Constraints {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts: Vec<String> = vec![];
+        if self.required {
+            parts.push("required".to_string());
+        }
+        if self.unique {
+            parts.push("unique".to_string());
+        }
+        if !self.min.is_empty() {
+            parts.push(format!("min {}", self.min));
+        }
+        if !self.max.is_empty() {
+            parts.push(format!("max {}", self.max));
+        }
+        if !self.pattern.is_empty() {
+            parts.push(format!("pattern {}", self.pattern));
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct Column {
+    name: String,
+    width: i32,
+    column_type: ColumnType,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    options: Vec<SelectOption>,
+    #[serde(default, skip_serializing_if = "NumberFormat::is_empty")]
+    number_format: NumberFormat,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    date_format: String,
+    /// The table file a relation column links to.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    relation: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rollup: Option<Rollup>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    formula: String,
+    /// The formula once it's parsed, see `parsed_formula`.
+    #[serde(skip)]
+    formula_expr: OnceCell<std::result::Result<formula::Expr, String>>,
+    /// What new cells start as, typed in like a value so it can be dynamic, like "today".
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    default: String,
+    #[serde(default, skip_serializing_if = "Constraints::is_empty")]
+    constraints: Constraints,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+impl Column {
+    fn has_options(&self) -> bool {
+        matches!(
+            self.column_type,
+            ColumnType::Multiselect | ColumnType::Select
+        )
+    }
+
+    fn has_dates(&self) -> bool {
+        matches!(
+            self.column_type,
+            ColumnType::Date
+                | ColumnType::DateRange
+                | ColumnType::CreatedTime
+                | ColumnType::EditedTime
+        )
+    }
+
+    fn option(&self, name: &str) -> Option<&SelectOption> {
+        self.options.iter().find(|option| option.name == name)
+    }
+
+    fn option_pair(&self, name: &str) -> ColorPair {
+        match self.option(name) {
+            Some(option) => option.color.pair(),
+            None => BLUE_PAIR,
+        }
+    }
+
+    /// Computed columns can't be edited, their values come from other cells.
+    fn is_computed(&self) -> bool {
+        matches!(self.column_type, ColumnType::Rollup | ColumnType::Formula)
+    }
+
+    fn default_cell(&self) -> Cell {
+        if self.is_read_only() {
+            return Cell::Empty;
+        }
+        Cell::parse(&self.default, self)
+    }
+
+    /// The column's formula parsed once, rather than for every cell it's worked out for.
+    fn parsed_formula(&self) -> &std::result::Result<formula::Expr, String> {
+        self.formula_expr
+            .get_or_init(|| formula::parse(&self.formula))
+    }
+
+    fn set_formula(&mut self, formula: &str) {
+        self.formula = formula.to_string();
+        self.formula_expr = OnceCell::new();
+    }
+
+    fn set_default(&mut self, raw: &str) -> std::result::Result<(), String> {
+        if self.is_read_only() {
+            return Err(format!("Error: Column '{}' is read-only", self.name));
+        }
+        self.validate(&Cell::parse(raw, self))?;
+        self.default = raw.trim().to_string();
+
+        Ok(())
+    }
+
+    /// Read-only columns can't be edited, they are computed or kept up to date by ion, or of a type
+    /// this ion doesn't know.
+    fn is_read_only(&self) -> bool {
+        self.is_computed()
+            || matches!(
+                self.column_type,
+                ColumnType::CreatedTime | ColumnType::EditedTime | ColumnType::Unknown(_)
+            )
+    }
+
+    fn number_format(&self) -> NumberFormat {
+        match &self.rollup {
+            Some(rollup)
+                if self.number_format.is_empty()
+                    && rollup.function == RollupFunction::PercentChecked =>
+            {
+                NumberFormat::from_str("0%").unwrap()
+            }
+            _ => self.number_format.clone(),
+        }
+    }
+
+    fn date_format(&self) -> &str {
+        if self.date_format.is_empty() {
+            DATE_FORMAT
+        } else {
+            &self.date_format
+        }
+    }
+
+    fn option_index(&self, name: &str) -> Option<usize> {
+        self.options.iter().position(|option| option.name == name)
+    }
+
+    /// Orders two of the column's values from first to last when sorting ascending, options in
+    /// the column's order of them. Values of different kinds go in the order of their kinds, so
+    /// any values can be sorted.
+    fn cmp_values(&self, a: &Cell, b: &Cell) -> Ordering {
+        let option_order = |names: &[String]| -> Vec<usize> {
+            names
+                .iter()
+                .map(|name| self.option_index(name).unwrap_or(usize::MAX))
+                .collect()
+        };
+        // by the days they're on, with a day before the times on it
+        let date_order = |cell: &Cell| {
+            let time = match cell {
+                Cell::DateTime(date_time) => Some(*date_time),
+                _ => None,
+            };
+            cell.days().map(|(start, end)| (start, end, time))
+        };
+        a.kind_rank()
+            .cmp(&b.kind_rank())
+            .then_with(|| match (a, b) {
+                (Cell::Select(_) | Cell::Options(_), Cell::Select(_) | Cell::Options(_)) => {
+                    option_order(a.option_names()).cmp(&option_order(b.option_names()))
+                }
+                (Cell::Text(a), Cell::Text(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+                (Cell::Number(a, _), Cell::Number(b, _)) => a.total_cmp(b),
+                (Cell::Boolean(a), Cell::Boolean(b)) => a.cmp(b),
+                (Cell::Relation(a), Cell::Relation(b)) => a.cmp(b),
+                _ => date_order(a)
+                    .cmp(&date_order(b))
+                    .then_with(|| a.to_string().cmp(&b.to_string())),
+            })
+    }
+
+    /// The ways the column's values can be tested in a filter.
+    fn filter_ops(&self) -> &'static [FilterOp] {
+        match self.column_type {
+            ColumnType::Number => &NUMBER_OPS,
+            ColumnType::Date
+            | ColumnType::DateRange
+            | ColumnType::CreatedTime
+            | ColumnType::EditedTime => &DATE_OPS,
+            ColumnType::Boolean => &BOOLEAN_OPS,
+            ColumnType::Select => &SELECT_OPS,
+            ColumnType::Multiselect => &MULTISELECT_OPS,
+            ColumnType::Rollup | ColumnType::Formula => &COMPUTED_OPS,
+            _ => &TEXT_OPS,
+        }
+    }
+
+    /// What a filter's `raw` value is compared with, dates and numbers are read as such so they
+    /// compare by value.
+    fn filter_target(&self, raw: &str) -> Cell {
+        let as_date = || parse_date(raw, self.date_format()).map(Cell::Date);
+        let as_number = || parse_number(raw).map(Cell::number);
+        let target = match self.column_type {
+            ColumnType::Date
+            | ColumnType::DateRange
+            | ColumnType::CreatedTime
+            | ColumnType::EditedTime => as_date(),
+            _ => as_number().or_else(as_date),
+        };
+
+        target.unwrap_or_else(|| Cell::Text(raw.to_string()))
+    }
+
+    /// Checks that `raw` is something `op` can test the column's values against.
+    fn check_filter_value(&self, op: &FilterOp, raw: &str) -> std::result::Result<(), String> {
+        let target = self.filter_target(raw);
+        match op {
+            _ if !op.takes_value() => Ok(()),
+            _ if raw.is_empty() => Err(format!("Error: '{}' needs a value", op)),
+            FilterOp::Within => match raw.trim().parse::<u32>() {
+                Ok(days) => match Local::now()
+                    .date_naive()
+                    .checked_add_days(Days::new(days as u64))
+                {
+                    Some(_) => Ok(()),
+                    None => Err(format!("Error: {} days from today is out of range", days)),
+                },
+                Err(_) => Err(format!("Error: '{}' is not a number of days", raw)),
+            },
+            FilterOp::Before | FilterOp::After if !matches!(target, Cell::Date(_)) => {
+                Err(format!("Error: '{}' is not a date", raw))
+            }
+            FilterOp::Less
+            | FilterOp::LessOrEqual
+            | FilterOp::Greater
+            | FilterOp::GreaterOrEqual
+                if matches!(target, Cell::Text(_)) =>
+            {
+                Err(format!("Error: '{}' is not a number or date", raw))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Adds any options used in `cell` that the column doesn't know about yet.
+    fn register_options(&mut self, cell: &Cell) {
+        for name in cell.option_names() {
+            if self.option(name).is_none() {
+                let color = OPTION_COLORS[self.options.len() % OPTION_COLORS.len()].clone();
+                self.options.push(SelectOption {
+                    name: name.clone(),
+                    color,
+                    extra: Map::new(),
+                });
+            }
+        }
+    }
+
+    /// Checks a value typed in by the user before it is put in this column.
+    fn validate(&self, cell: &Cell) -> std::result::Result<(), String> {
+        match cell {
+            Cell::Invalid(raw) if matches!(self.column_type, ColumnType::DateRange) => {
+                match parse_date_range(raw, self.date_format()) {
+                    Some(_) => Err(format!("Error: '{}' ends before it starts", raw)),
+                    None => Err(format!("Type Error: '{}' is not a valid date range", raw)),
+                }
+            }
+            Cell::Invalid(raw) => Err(format!(
+                "Type Error: '{}' is not a valid {}",
+                raw, self.column_type
+            )),
+            Cell::Select(name) if self.option(name).is_none() => Err(format!(
+                "Error: '{}' is not an option of '{}'",
+                name, self.name
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// A min or max constraint as a value that cells can be compared to.
+    fn bound(&self, raw: &str) -> Option<Cell> {
+        match self.column_type {
+            _ if raw.is_empty() => None,
+            ColumnType::Number => parse_number(raw).map(Cell::number),
+            ColumnType::Date | ColumnType::DateRange => {
+                parse_date(raw, self.date_format()).map(Cell::Date)
+            }
+            _ => None,
+        }
+    }
+
+    /// Checks a valid value against the column's constraints, except for `unique` which needs the
+    /// rest of the table.
+    fn check_constraints(&self, cell: &Cell) -> std::result::Result<(), String> {
+        let constraints = &self.constraints;
+        if constraints.required && *cell == Cell::Empty {
+            return Err(format!("Error: '{}' is required", self.name));
+        }
+        let (first, last) = match cell {
+            Cell::DateRange(start, end) => (Cell::Date(*start), Cell::Date(*end)),
+            _ => (cell.clone(), cell.clone()),
+        };
+        let shown = cell_as_col_type(cell, self).0;
+        if let Some(min) = self.bound(&constraints.min) {
+            if formula::compare(&first, &min) == Some(Ordering::Less) {
+                return Err(format!(
+                    "Error: '{}' is below the min '{}' of '{}'",
+                    shown, constraints.min, self.name
+                ));
+            }
+        }
+        if let Some(max) = self.bound(&constraints.max) {
+            if formula::compare(&last, &max) == Some(Ordering::Greater) {
+                return Err(format!(
+                    "Error: '{}' is above the max '{}' of '{}'",
+                    shown, constraints.max, self.name
+                ));
+            }
+        }
+        if !constraints.pattern.is_empty() && *cell != Cell::Empty {
+            let pattern = whole_match_regex(&constraints.pattern)?;
+            if !pattern.is_match(&cell.to_string()) {
+                return Err(format!(
+                    "Error: '{}' doesn't match the pattern '{}' of '{}'",
+                    cell, constraints.pattern, self.name
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Turns `required` and `unique` on or off, or sets (or with no value, clears) a min, max or
+    /// pattern.
+    fn set_constraint(&mut self, name: &str, value: &str) -> std::result::Result<(), String> {
+        match name {
+            _ if self.is_read_only() => {
+                return Err(format!("Error: Column '{}' is read-only", self.name));
+            }
+            "required" if value.is_empty() => self.constraints.required ^= true,
+            "unique" if value.is_empty() => self.constraints.unique ^= true,
+            "min" | "max" if !value.is_empty() && self.bound(value).is_none() => {
+                return Err(match self.column_type {
+                    ColumnType::Number | ColumnType::Date | ColumnType::DateRange => {
+                        format!("Type Error: '{}' is not a valid {}", value, self.column_type)
+                    }
+                    _ => format!("Error: Column '{}' can't have a {}", self.name, name),
+                });
+            }
+            "min" => self.constraints.min = value.to_string(),
+            "max" => self.constraints.max = value.to_string(),
+            "pattern" => {
+                whole_match_regex(value)?;
+                self.constraints.pattern = value.to_string();
+            }
+            _ => {
+                return Err("Usage Error: '(con|constraint) (required|unique|min [value]|max [value]|pattern [regex])'".to_string())
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A regex only matching all of a value, for a pattern constraint.
+fn whole_match_regex(pattern: &str) -> std::result::Result<Regex, String> {
+    Regex::new(&format!("^(?:{})$", pattern))
+        .map_err(|_| format!("Error: '{}' is not a valid regex", pattern))
+}
+
+type RowId = u64;
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct Row {
+    id: RowId,
+    cells: Vec<Cell>,
+    /// The row's page, a markdown document shown under its fields.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    body: String,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+impl Index<usize> for Row {
+    type Output = Cell;
+
+    fn index(&self, col: usize) -> &Cell {
+        &self.cells[col]
+    }
+}
+
+impl IndexMut<usize> for Row {
+    fn index_mut(&mut self, col: usize) -> &mut Cell {
+        &mut self.cells[col]
+    }
+}
+
+/// One key of a view's sort, rows are ordered by it when the keys before it are equal.
+#[derive(Serialize, Deserialize, Clone)]
+struct Sort {
+    /// The name of the column sorted by.
+    column: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    descending: bool,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+/// How a filter condition tests a column's value.
+#[derive(
+    Serialize, Deserialize, strum_macros::Display, strum_macros::EnumString, Clone, PartialEq,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(ascii_case_insensitive)]
+enum FilterOp {
+    #[strum(serialize = "contains")]
+    Contains,
+    #[strum(serialize = "doesn't contain")]
+    NotContains,
+    #[strum(to_string = "is", serialize = "=", serialize = "on")]
+    Is,
+    #[strum(to_string = "is not", serialize = "!=")]
+    IsNot,
+    #[strum(serialize = "<")]
+    Less,
+    #[strum(serialize = "<=")]
+    LessOrEqual,
+    #[strum(serialize = ">")]
+    Greater,
+    #[strum(serialize = ">=")]
+    GreaterOrEqual,
+    #[strum(serialize = "before")]
+    Before,
+    #[strum(serialize = "after")]
+    After,
+    /// On a date from today to the given number of days from now.
+    #[strum(serialize = "within")]
+    Within,
+    #[strum(serialize = "is checked")]
+    Checked,
+    #[strum(serialize = "isn't checked")]
+    Unchecked,
+    #[strum(serialize = "is empty")]
+    Empty,
+    #[strum(serialize = "isn't empty")]
+    NotEmpty,
+    /// Lets every row pass.
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+const TEXT_OPS: [FilterOp; 6] = [
+    FilterOp::Contains,
+    FilterOp::NotContains,
+    FilterOp::Is,
+    FilterOp::IsNot,
+    FilterOp::Empty,
+    FilterOp::NotEmpty,
+];
+
+const NUMBER_OPS: [FilterOp; 8] = [
+    FilterOp::Is,
+    FilterOp::IsNot,
+    FilterOp::Less,
+    FilterOp::LessOrEqual,
+    FilterOp::Greater,
+    FilterOp::GreaterOrEqual,
+    FilterOp::Empty,
+    FilterOp::NotEmpty,
+];
+
+const DATE_OPS: [FilterOp; 6] = [
+    FilterOp::Is,
+    FilterOp::Before,
+    FilterOp::After,
+    FilterOp::Within,
+    FilterOp::Empty,
+    FilterOp::NotEmpty,
+];
+
+const BOOLEAN_OPS: [FilterOp; 2] = [FilterOp::Checked, FilterOp::Unchecked];
+
+const SELECT_OPS: [FilterOp; 4] = [
+    FilterOp::Is,
+    FilterOp::IsNot,
+    FilterOp::Empty,
+    FilterOp::NotEmpty,
+];
+
+const MULTISELECT_OPS: [FilterOp; 4] = [
+    FilterOp::Contains,
+    FilterOp::NotContains,
+    FilterOp::Empty,
+    FilterOp::NotEmpty,
+];
+
+/// Computed columns can give any kind of value, so they can be tested in most ways.
+const COMPUTED_OPS: [FilterOp; 10] = [
+    FilterOp::Is,
+    FilterOp::IsNot,
+    FilterOp::Contains,
+    FilterOp::Less,
+    FilterOp::Greater,
+    FilterOp::Before,
+    FilterOp::After,
+    FilterOp::Within,
+    FilterOp::Empty,
+    FilterOp::NotEmpty,
+];
+
+impl FilterOp {
+    fn takes_value(&self) -> bool {
+        !matches!(
+            self,
+            FilterOp::Checked | FilterOp::Unchecked | FilterOp::Empty | FilterOp::NotEmpty
+        )
+    }
+}
+
+/// A test of one column's value that rows have to pass to be shown.
+#[derive(Serialize, Deserialize, Clone)]
+struct Condition {
+    /// The name of the column tested.
+    column: String,
+    op: FilterOp,
+    /// What the value is tested against, typed in like a value so dates can be like "today".
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    value: String,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.op {
+            FilterOp::Within => write!(f, "{} within {} days", self.column, self.value),
+            op if op.takes_value() => write!(f, "{} {} {}", self.column, op, self.value),
+            op => write!(f, "{} {}", self.column, op),
+        }
+    }
+}
+
+/// How a view draws its rows.
+#[derive(
+    Serialize,
+    Deserialize,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    Clone,
+    Default,
+    PartialEq,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+enum Layout {
+    #[default]
+    Table,
+    /// Cards in lanes, one for each option of a select or multiselect column.
+    Board,
+    /// A month with the rows on each of its days by a date column.
+    Calendar,
+    /// Drawn as a table.
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+impl Layout {
+    fn is_table(&self) -> bool {
+        *self == Layout::Table
+    }
+}
+
+/// A lane of a board, the rows with one of its column's options or, without an option, none.
+struct Lane {
+    option: Option<String>,
+    rows: Vec<usize>,
+}
+
+/// Rows with the same value in the column the current view groups by.
+struct Group {
+    /// The value as it's stored, which is how collapsed groups are saved.
+    key: String,
+    name: String,
+    rows: Vec<usize>,
+    collapsed: bool,
+}
+
+/// A formula rows have to be true for to be shown, parsed once when it's set rather than each
+/// time the rows are filtered.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(from = "String", into = "String")]
+struct Query {
+    text: String,
+    /// Nothing when the text is empty or isn't a formula, then every row passes.
+    expr: Option<formula::Expr>,
+}
+
+impl Query {
+    fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    fn uses(&self, column: &str) -> bool {
+        self.expr
+            .as_ref()
+            .is_some_and(|expr| formula::columns(expr).iter().any(|name| name == column))
+    }
+
+    fn rename_column(&mut self, old: &str, new: &str) {
+        let renamed = formula::rename_column(&self.text, old, new);
+        if renamed != self.text {
+            *self = Query::from(renamed);
+        }
+    }
+}
+
+impl From<String> for Query {
+    fn from(text: String) -> Query {
+        let expr = formula::parse(&text).ok().filter(|_| !text.is_empty());
+        Query { text, expr }
+    }
+}
+
+impl From<Query> for String {
+    fn from(query: Query) -> String {
+        query.text
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// A saved way of looking at a table, which of its columns are shown, in what order and which
+/// of its rows are shown, how they're sorted and grouped.
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct View {
+    name: String,
+    /// Groups of conditions, rows are shown when they pass every condition of any one group.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    filter: Vec<Vec<Condition>>,
+    /// A formula rows are only shown when true for, along with the filter, see `:filter`.
+    #[serde(default, skip_serializing_if = "Query::is_empty")]
+    query: Query,
+    /// The name of the column rows are grouped by.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    group_by: String,
+    /// The values of the groups whose rows are hidden.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    collapsed: Vec<String>,
+    #[serde(default, skip_serializing_if = "Layout::is_table")]
+    layout: Layout,
+    /// The name of the select or multiselect column a board's lanes come from.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    board_by: String,
+    /// Names of the columns shown on a board's cards under their titles.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    card_fields: Vec<String>,
+    /// The name of the date column a calendar puts rows on days by.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    calendar_by: String,
+    /// The keys rows are sorted by, most important first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    sorts: Vec<Sort>,
+    /// Names of the columns in the order they're drawn, columns missing from it come after.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    order: Vec<String>,
+    /// Names of the columns that aren't drawn.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    hidden: Vec<String>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+impl View {
+    fn named(name: &str) -> View {
+        View {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+fn label(text: &str, y: i32, x: i32, pair: i16) {
+    mv(y, x);
+    attron(COLOR_PAIR(pair));
+    addstr(text);
+    attroff(COLOR_PAIR(pair));
+}
+
+/// Tables open in this session other than the one being looked at, by path.
+type OtherTables = HashMap<String, Table>;
+
+#[derive(Serialize, Deserialize, strum_macros::Display)]
+enum TableFocus {
+    Element,
+    NewElement,
+    View,
+    Sort,
+    Filter,
+    Column,
+    NewColumn,
+    /// Last, as it's also where a table opens when it was saved in a mode this ion doesn't have.
+    #[serde(other)]
+    Table,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Table {
+    /// The version of the file format the table was saved in, see `MIGRATIONS`.
+    #[serde(default)]
+    version: u64,
+    title: String,
+    subtitle: String,
+    #[serde(default)]
+    views: Vec<View>,
+    #[serde(default)]
+    curr_view: usize,
+    columns: Vec<Column>,
+    data: Vec<Row>,
+    #[serde(default)]
+    next_row_id: RowId,
+    #[serde(skip)]
+    curr_row: usize,
+    /// The row the cursor is on, saved by id so it stays on that row when rows move.
+    #[serde(default)]
+    curr_row_id: RowId,
+    /// The rows the current view shows, in the order it shows them, see `refresh_view`.
+    #[serde(skip)]
+    shown_rows: Vec<usize>,
+    /// The groups of the rows passing the current view's filter, when it groups them.
+    #[serde(skip)]
+    groups: Vec<Group>,
+    /// The condition of the current view's filter being edited, counted across its groups.
+    #[serde(skip)]
+    curr_cond: usize,
+    /// The lane of the board the cursor is in, the card it's on is the current row.
+    #[serde(skip)]
+    curr_lane: usize,
+    /// The day of the calendar the cursor is on, the month around it is the one shown.
+    #[serde(skip)]
+    curr_day: NaiveDate,
+    /// A filter typed with `:filter` that hasn't been saved in a view, it applies to every view.
+    #[serde(skip)]
+    unsaved_query: Query,
+    curr_col: usize,
+    num_mode: NumMode,
+    table_focus: TableFocus,
+    path: String,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+// the to_*_mode methods switch focus, they don't convert the table
+#[allow(clippy::wrong_self_convention)]
+impl Table {
+    fn draw_title(&self) {
+        label(&self.title, 0, 0, WHITE_PAIR);
+    }
+
+    fn draw_subtitle(&self) {
+        label(&self.subtitle, 2, 4, WHITE_PAIR);
+    }
+
+    /// Draws the names of the views, numbered in View focus so they can be switched to by number,
+    /// with a name being typed in place of the one it's for.
+    fn draw_views(&self, motion_num: usize, input_mode: InputMode, input_str: &str) {
+        let in_view_focus = matches!(self.table_focus, TableFocus::View);
+        let typing = in_view_focus && input_mode == InputMode::Text;
+        label("View: ", 3, 4, WHITE_PAIR);
+        for (view_num, view) in self.views.iter().enumerate() {
+            if view_num > 0 {
+                addstr(" . ");
+            }
+            if in_view_focus {
+                addstr(&format!("{}|", view_num + 1));
+            }
+            let renaming = typing && motion_num == 1 && view_num == self.curr_view;
+            let name = if renaming { input_str } else { &view.name };
+            let pair = if view_num == self.curr_view && !(typing && motion_num == 0) {
+                INV_WHITE_PAIR
+            } else {
+                WHITE_PAIR
+            };
+            attron(COLOR_PAIR(pair));
+            addstr(name);
+            if renaming {
+                addstr(" ");
+            }
+            attroff(COLOR_PAIR(pair));
+        }
+        // the view being made
+        if typing && motion_num == 0 {
+            addstr(&format!(" . {}|", self.views.len() + 1));
+            attron(COLOR_PAIR(INV_WHITE_PAIR));
+            addstr(&format!("{} ", input_str));
+            attroff(COLOR_PAIR(INV_WHITE_PAIR));
+        }
+    }
+
+    /// Draws the current view's sort keys in order, the current column's one highlighted.
+    fn draw_sorts(&self) {
+        label("Sort: ", 3, 4, WHITE_PAIR);
+        if self.view().sorts.is_empty() {
+            addstr("none");
+        }
+        for (sort_num, sort) in self.view().sorts.iter().enumerate() {
+            if sort_num > 0 {
+                addstr(" . ");
+            }
+            let pair = if self.col_num(&sort.column) == Some(self.curr_col) {
+                INV_WHITE_PAIR
+            } else {
+                WHITE_PAIR
+            };
+            attron(COLOR_PAIR(pair));
+            addstr(&format!(
+                "{}|{} {}",
+                sort_num + 1,
+                sort.column,
+                if sort.descending { "desc" } else { "asc" }
+            ));
+            attroff(COLOR_PAIR(pair));
+        }
+    }
+
+    /// Draws the current view's filter under the table, one condition a line, with the fields of
+    /// the current one numbered.
+    fn draw_filter(&self, motion_num: usize, input_mode: InputMode, input_str: &str) {
+        let start_y = 10 + self.num_lines() as i32;
+        label("Filter:", start_y, 4, WHITE_PAIR);
+        if self.view().filter.is_empty() && self.view().query.is_empty() {
+            addstr(" none");
+        }
+        let mut line = start_y;
+        if !self.view().query.is_empty() {
+            line += 1;
+            label(&format!("{:>7} ", "query"), line, 4, WHITE_PAIR);
+            addstr(&self.view().query.text);
+        }
+        let mut cond_num = 0;
+        for (group_num, group) in self.view().filter.iter().enumerate() {
+            for (pos, cond) in group.iter().enumerate() {
+                line += 1;
+                let joiner = match (group_num, pos) {
+                    (0, 0) => "where",
+                    (_, 0) => "or",
+                    _ => "and",
+                };
+                label(&format!("{:>7} ", joiner), line, 4, WHITE_PAIR);
+                if cond_num != self.curr_cond {
+                    addstr(&cond.to_string());
+                    cond_num += 1;
+                    continue;
+                }
+                let mut fields = vec![cond.column.clone(), cond.op.to_string()];
+                if cond.op.takes_value() {
+                    fields.push(cond.value.clone());
+                }
+                for (field_num, field) in fields.iter().enumerate() {
+                    let pair = if input_mode == InputMode::Text && motion_num == field_num + 1 {
+                        WHITE_PAIR
+                    } else {
+                        INV_WHITE_PAIR
+                    };
+                    attron(COLOR_PAIR(pair));
+                    addstr(&format!("[{}|{}]", field_num + 1, field));
+                    attroff(COLOR_PAIR(pair));
+                    addstr(" ");
+                }
+                if input_mode == InputMode::Text {
+                    addstr(&format!("-> {}", input_str));
+                }
+                cond_num += 1;
+            }
+        }
+        if input_mode != InputMode::Text {
+            return;
+        }
+        let Some(col) = self
+            .cond_pos(self.curr_cond)
+            .and_then(|(group, pos)| self.col_num(&self.view().filter[group][pos].column))
+        else {
+            return;
+        };
+        let column = &self.columns[col];
+        match motion_num {
+            2 => {
+                let ops: Vec<String> = column
+                    .filter_ops()
+                    .iter()
+                    .map(|op| op.to_string())
+                    .collect();
+                label(&ops.join(" . "), line + 2, 12, WHITE_PAIR);
+            }
+            3 => self.draw_choices(column, input_str, line + 2),
+            _ => {}
+        }
+    }
+
+    /// Draws the current view's board, a lane for each of its column's options with a card for
+    /// each row in it.
+    fn draw_board(&self, others: &OtherTables) {
+        let mut screen_h = 0;
+        let mut screen_w = 0;
+        getmaxyx(stdscr(), &mut screen_h, &mut screen_w);
+        let Some(col) = self.col_num(&self.view().board_by) else {
+            return;
+        };
+        let column = &self.columns[col];
+        let lanes = self.lanes();
+        let lane_width = ((screen_w - 4) / lanes.len() as i32 - 2).clamp(12, 30) as usize;
+        // lanes that don't fit are scrolled past, so the current one is on screen
+        let fits = max((screen_w - 4) / (lane_width as i32 + 2), 1) as usize;
+        let first = (self.curr_lane + 1).saturating_sub(fits);
+        if first > 0 {
+            label("<", 5, 2, WHITE_PAIR);
+        }
+        if first + fits < lanes.len() {
+            label(
+                ">",
+                5,
+                2 + fits as i32 * (lane_width as i32 + 2),
+                WHITE_PAIR,
+            );
+        }
+        let fields: Vec<usize> = self
+            .view()
+            .card_fields
+            .iter()
+            .filter_map(|name| self.col_num(name))
+            .collect();
+        let card_height = fields.len() as i32 + 3;
+        for (lane_num, lane) in lanes.iter().enumerate().skip(first).take(fits) {
+            let x = 4 + (lane_num - first) as i32 * (lane_width as i32 + 2);
+            let (name, pair) = match &lane.option {
+                Some(option) => (option.clone(), column.option_pair(option)),
+                None => (format!("No {}", column.name), WHITE_PAIR),
+            };
+            let attr = if lane_num == self.curr_lane {
+                A_UNDERLINE()
+            } else {
+                A_NORMAL()
+            };
+            attron(attr);
+            label(&fit_to_sizel(&name, lane_width - 5, ' '), 5, x, pair);
+            addstr(&fit_to_sizer(&format!("{} ", lane.rows.len()), 5, ' '));
+            attroff(attr);
+
+            let mut y = 6;
+            for &row in lane.rows.iter() {
+                if y + card_height > screen_h - 2 {
+                    label("..", y, x, WHITE_PAIR);
+                    break;
+                }
+                let border = format!("+{}+", n_of_c(lane_width - 2, '-'));
+                label(&border, y, x, WHITE_PAIR);
+                let curr = lane_num == self.curr_lane && row == self.curr_row;
+                label("|", y + 1, x, WHITE_PAIR);
+                label(
+                    &fit_to_sizel(&self.row_title(row), lane_width - 2, ' '),
+                    y + 1,
+                    x + 1,
+                    if curr { INV_WHITE_PAIR } else { WHITE_PAIR },
+                );
+                addstr("|");
+                for (field_num, &field) in fields.iter().enumerate() {
+                    let (value, pair) = self.display_cell(row, field, others);
+                    let value = match &self.data[row][field] {
+                        Cell::Options(options) => options.join(", "),
+                        _ => value,
+                    };
+                    let line = y + 2 + field_num as i32;
+                    label("|", line, x, WHITE_PAIR);
+                    label(
+                        &fit_to_sizel(
+                            &format!("{}: {}", self.columns[field].name, value),
+                            lane_width - 2,
+                            ' ',
+                        ),
+                        line,
+                        x + 1,
+                        pair,
+                    );
+                    addstr("|");
+                }
+                label(&border, y + card_height - 1, x, WHITE_PAIR);
+                y += card_height;
+            }
+        }
+    }
+
+    /// Draws the month of the calendar's current day, a week a line with the titles of the rows on
+    /// each day, numbered on the current one.
+    fn draw_calendar(&self) {
+        let mut screen_h = 0;
+        let mut screen_w = 0;
+        getmaxyx(stdscr(), &mut screen_h, &mut screen_w);
+        let Some(first) = self.curr_day.with_day(1) else {
+            return;
+        };
+        let Some(next_first) = first.checked_add_months(Months::new(1)) else {
+            return;
+        };
+        let offset = first.weekday().num_days_from_sunday() as i32;
+        // weeks start on sunday, with the end of the month before
+        let Some(start) = first.checked_sub_days(Days::new(offset as u64)) else {
+            return;
+        };
+        let num_weeks = (offset + (next_first - first).num_days() as i32 + 6) / 7;
+        let day_width = ((screen_w - 5) / 7 - 1).clamp(8, 24) as usize;
+        let day_height = ((screen_h - 10) / num_weeks - 1).clamp(2, 8);
+        let border = format!("+{}", format!("{}+", n_of_c(day_width, '-')).repeat(7));
+
+        attron(A_BOLD());
+        label(&self.curr_day.format("%B %Y").to_string(), 5, 4, WHITE_PAIR);
+        attroff(A_BOLD());
+        mv(6, 4);
+        for name in ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"] {
+            addstr(&format!(" {}", fit_to_sizel(name, day_width, ' ')));
+        }
+        label(&border, 7, 4, WHITE_PAIR);
+        for week in 0..num_weeks {
+            let top = 8 + week * (day_height + 1);
+            for line in 0..day_height {
+                label("|", top + line, 4, WHITE_PAIR);
+                for day in 0..7 {
+                    let Some(date) = start.checked_add_days(Days::new((week * 7 + day) as u64))
+                    else {
+                        continue;
+                    };
+                    let (text, pair) = self.day_line(date, line as usize, day_height as usize);
+                    let attr = if date.month() == first.month() {
+                        A_NORMAL()
+                    } else {
+                        A_DIM()
+                    };
+                    mv(top + line, 5 + day * (day_width as i32 + 1));
+                    attron(attr);
+                    attron(COLOR_PAIR(pair));
+                    addstr(&fit_to_sizel(&text, day_width, ' '));
+                    attroff(COLOR_PAIR(pair));
+                    attroff(attr);
+                    addstr("|");
+                }
+            }
+            label(&border, top + day_height, 4, WHITE_PAIR);
+        }
+    }
+
+    /// The `line`th of the `height` lines of a day in the calendar, its number then its rows'
+    /// titles, with how many more there are on the last when they don't fit.
+    fn day_line(&self, date: NaiveDate, line: usize, height: usize) -> (String, i16) {
+        let curr_day = date == self.curr_day;
+        if line == 0 {
+            let pair = date_color(date);
+            // turns from normal to inverse
+            return (format!("{:>2}", date.day()), pair + curr_day as i16);
+        }
+        let rows = self.day_rows(date);
+        if line == height - 1 && rows.len() > height - 1 {
+            return (format!("+{} more", rows.len() - line + 1), WHITE_PAIR);
+        }
+        let Some(&row) = rows.get(line - 1) else {
+            return ("".to_string(), WHITE_PAIR);
+        };
+        if !curr_day {
+            return (self.row_title(row), WHITE_PAIR);
+        }
+        let pair = if row == self.curr_row {
+            INV_WHITE_PAIR
+        } else {
+            WHITE_PAIR
+        };
+        (format!("{} {}", line, self.row_title(row)), pair)
+    }
+
+    fn draw_headers(&self) {
+        let num_col_size: usize = (self.data.len() as f32).log10() as usize + 1;
+        {
+            label("+", 4, 4, WHITE_PAIR);
+            addstr(&n_of_c(num_col_size + 2, '-'));
+            addstr("+");
+
+            for col in self.shown_cols().into_iter().map(|col| &self.columns[col]) {
+                addstr(&format!("{}+", n_of_c((col.width + 2) as usize, '-')));
+            }
+        }
+        {
+            label(
+                &format!("| {} ", n_of_c(num_col_size, ' ')),
+                5,
+                4,
+                WHITE_PAIR,
+            );
+
+            for col_num in self.shown_cols() {
+                let col = &self.columns[col_num];
+                let pair = match self.table_focus {
+                    TableFocus::Column | TableFocus::Sort | TableFocus::Filter => {
+                        if col_num == self.curr_col {
+                            INV_WHITE_PAIR
+                        } else {
+                            WHITE_PAIR
+                        }
+                    }
+                    _ => WHITE_PAIR,
+                };
+                addstr("| ");
+                attron(COLOR_PAIR(pair));
+                addstr(&col.name);
+                addstr(column_symbols(&col.column_type));
+                attroff(COLOR_PAIR(pair));
+                addstr(&n_of_c(col.width as usize - col.name.len() - 1, ' '));
+                addstr(" ");
+            }
+            addstr("|");
+        }
+        {
+            // TODO is duplicate of 1st block
+            label("+", 6, 4, WHITE_PAIR);
+            addstr(&n_of_c(num_col_size + 2, '='));
+            addstr("+");
+
+            for col in self.shown_cols().into_iter().map(|col| &self.columns[col]) {
+                addstr(&format!("{}+", n_of_c((col.width + 2) as usize, '=')));
+            }
+        }
+    }
+
+    fn draw_data(&self, others: &OtherTables) {
+        let start_y: i32 = 7;
+        let cols = self.shown_cols();
+        let mut y = start_y;
+        let mut line = 0;
+        // without groups all the rows are drawn as one
+        for group_num in 0..max(self.groups.len(), 1) {
+            let rows: &[usize] = match self.groups.get(group_num) {
+                Some(group) => {
+                    self.draw_group(group_num, group, y, &cols);
+                    y += 1;
+                    if group.collapsed {
+                        continue;
+                    }
+                    &group.rows
+                }
+                None => &self.shown_rows,
+            };
+            for &row_num in rows {
+                self.draw_row(row_num, line, y, &cols, others);
+                line += 1;
+                y += 1;
+            }
+        }
+    }
+
+    /// Draws the line naming a group across the table.
+    fn draw_group(&self, group_num: usize, group: &Group, y: i32, cols: &[usize]) {
+        let num_col_size: usize = (self.data.len() as f32).log10() as usize + 1;
+        let width = cols
+            .iter()
+            .map(|col| self.columns[*col].width as usize + 3)
+            .sum::<usize>()
+            + num_col_size
+            + 1;
+        let text = format!(
+            "{} {}|{} ({})",
+            if group.collapsed { ">" } else { "v" },
+            group_num + 1,
+            group.name,
+            group.rows.len()
+        );
+        mv(y, 4);
+        addstr("| ");
+        attron(A_BOLD());
+        addstr(&fit_to_sizel(&text, width, ' '));
+        attroff(A_BOLD());
+        addstr("|");
+    }
+
+    /// Draws row `row_num` as the `line`th row of the table.
+    fn draw_row(&self, row_num: usize, line: usize, y: i32, cols: &[usize], others: &OtherTables) {
+        let num_col_size: usize = (self.data.len() as f32).log10() as usize + 1;
+        let pair: i16 = if row_num == self.curr_row {
+            INV_WHITE_PAIR
+        } else {
+            WHITE_PAIR
+        };
+        mv(y, 4);
+        attron(COLOR_PAIR(pair));
+        {
+            addstr("| ");
+            addstr(&fit_to_sizer(
+                &format!(
+                    "{} ",
+                    match self.num_mode {
+                        NumMode::Absolute => line + 1,
+                        NumMode::Relative => line.abs_diff(self.curr_line()),
+                    }
+                ),
+                num_col_size + 1,
+                ' ',
+            ));
+        }
+        for &col_num in cols.iter() {
+            let item = &self.data[row_num][col_num];
+            let col = &self.columns[col_num];
+            addstr("| ");
+            if let Cell::Options(_) | Cell::Select(_) = item {
+                let used = draw_tags(
+                    item.option_names(),
+                    col,
+                    col.width as usize,
+                    self.curr_row == row_num,
+                );
+                attron(COLOR_PAIR(pair));
+                addstr(&n_of_c(col.width as usize - used, ' '));
+                addstr(" ");
+                continue;
+            }
+            let (str_to_display, mut color_to_display) =
+                self.display_cell(row_num, col_num, others);
+            if self.curr_row == row_num {
+                color_to_display += 1; // turns from normal to inverse
+            }
+            attron(COLOR_PAIR(color_to_display));
+            addstr(&fit_to_sizel(&str_to_display, col.width as usize, ' '));
+            attroff(COLOR_PAIR(color_to_display));
+            attron(COLOR_PAIR(pair));
+            addstr(" ");
+        }
+        addstr("|");
+        attroff(COLOR_PAIR(pair));
+    }
+
+    fn draw_footer(&self) {
+        {
+            label("+", 7 + self.num_lines() as i32, 4, WHITE_PAIR);
+            let num_col_size: usize = (self.data.len() as f32).log10() as usize + 1;
+            addstr(&n_of_c(num_col_size + 2, '-'));
+            addstr("+");
+
+            for col in self.shown_cols().into_iter().map(|col| &self.columns[col]) {
+                addstr(&format!("{}+", n_of_c((col.width + 2) as usize, '-')));
+            }
+            let shown = if self.is_filtered() {
+                format!("{} of ", self.shown_rows.len())
+            } else {
+                "".to_string()
+            };
+            let unsaved = if self.unsaved_query.is_empty() {
+                "".to_string()
+            } else {
+                format!(" where {}", self.unsaved_query)
+            };
+            label(
+                &format!(
+                    "{}{} {}{}",
+                    shown,
+                    self.data.len(),
+                    if self.data.len() == 1 {
+                        "entry"
+                    } else {
+                        "entries"
+                    },
+                    unsaved
+                ),
+                8 + self.num_lines() as i32,
+                5,
+                WHITE_PAIR,
+            );
+        }
+    }
+
+    fn draw_elem(
+        &self,
+        motion_num: usize,
+        input_mode: InputMode,
+        input_str: &str,
+        others: &OtherTables,
+    ) {
+        label(&format!("Row {}", self.curr_row + 1), 4, 4, WHITE_PAIR);
+        let start_y: usize = 6;
+        for col_num in 0..self.columns.len() {
+            let item = &self.value(self.curr_row, col_num, others);
+            label(
+                &format!(
+                    "[{}|{}{}]",
+                    col_num + 1,
+                    self.columns[col_num].name,
+                    column_symbols(&self.columns[col_num].column_type)
+                ),
+                (start_y + col_num * 3) as i32,
+                4,
+                WHITE_PAIR,
+            );
+            match item {
+                Cell::Options(_) | Cell::Select(_) => {
+                    mv((start_y + col_num * 3 + 1) as i32, 6);
+                    draw_tags(
+                        item.option_names(),
+                        &self.columns[col_num],
+                        usize::MAX,
+                        false,
+                    );
+                }
+                Cell::Invalid(raw) => {
+                    label(raw, (start_y + col_num * 3 + 1) as i32, 6, BLUE_PAIR);
+                }
+                Cell::Relation(ids) => {
+                    mv((start_y + col_num * 3 + 1) as i32, 6);
+                    let linked = self.linked_table(&self.columns[col_num].relation, others);
+                    for (link_num, id) in ids.iter().enumerate() {
+                        if link_num > 0 {
+                            addstr(", ");
+                        }
+                        match linked.and_then(|linked| linked.row_num(*id)) {
+                            Some(row_num) => {
+                                let linked = linked.unwrap();
+                                addstr(&linked.row_title(row_num));
+                            }
+                            None => {
+                                attron(COLOR_PAIR(BLUE_PAIR));
+                                addstr("?");
+                                attroff(COLOR_PAIR(BLUE_PAIR));
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    let (str_to_display, item_color) =
+                        self.display_cell(self.curr_row, col_num, others);
+                    label(
+                        &str_to_display,
+                        (start_y + col_num * 3 + 1) as i32,
+                        6,
+                        item_color,
+                    );
+                }
+            }
+            if let InputMode::Text = input_mode {
+                if motion_num == col_num + 1 && self.is_multi_line_field(motion_num) {
+                    addstr(" -> (editing)");
+                } else if motion_num == col_num + 1 {
+                    addstr(&format!(" -> {}", input_str));
+                    attron(COLOR_PAIR(INV_WHITE_PAIR));
+                    addstr(" ");
+                    attroff(COLOR_PAIR(INV_WHITE_PAIR));
+                }
+            }
+        }
+        if let InputMode::Text = input_mode {
+            if motion_num > 0 && motion_num <= self.columns.len() {
+                self.draw_choices(
+                    &self.columns[motion_num - 1],
+                    input_str,
+                    (start_y + (motion_num - 1) * 3 + 2) as i32,
+                );
+            }
+        }
+        if self
+            .columns
+            .iter()
+            .any(|col| col.column_type == ColumnType::LongText)
+        {
+            self.draw_long_text_pane(motion_num, input_mode, input_str);
+        }
+        if matches!(self.table_focus, TableFocus::Element) {
+            let y = (start_y + self.columns.len() * 3) as i32;
+            self.draw_page(motion_num, input_mode, input_str, y);
+        }
+    }
+
+    /// Whether `field` of the row being looked at is typed in over many lines, it is long text or
+    /// the field after the last one, which is the row's page.
+    fn is_multi_line_field(&self, field: usize) -> bool {
+        match self.table_focus {
+            TableFocus::Element if field == self.columns.len() + 1 => true,
+            TableFocus::Element | TableFocus::NewElement => {
+                field > 0
+                    && field <= self.columns.len()
+                    && self.columns[field - 1].column_type == ColumnType::LongText
+            }
+            _ => false,
+        }
+    }
+
+    /// Draws the page of the row being looked at under its fields.
+    fn draw_page(&self, motion_num: usize, input_mode: InputMode, input_str: &str, y: i32) {
+        let mut screen_h = 0;
+        let mut screen_w = 0;
+        getmaxyx(stdscr(), &mut screen_h, &mut screen_w);
+        let has_pane = self
+            .columns
+            .iter()
+            .any(|col| col.column_type == ColumnType::LongText);
+        let width = if has_pane {
+            screen_w / 2 - 8
+        } else {
+            screen_w - 8
+        };
+        let field = self.columns.len() + 1;
+        let height = max(screen_h - 3 - y - 1, 1) as usize;
+
+        if input_mode == InputMode::Text && motion_num == field {
+            let title = format!("[{}|page]", field);
+            draw_editor(&title, input_str, y, 4, max(width, 10) as usize, height);
+        } else {
+            label(&format!("[{}|page]", field), y, 4, WHITE_PAIR);
+            markdown::draw(
+                &self.data[self.curr_row].body,
+                y + 1,
+                6,
+                max(width, 10) as usize,
+                height,
+            );
+        }
+    }
+
+    /// Draws the row's long text on the right half of the screen as markdown, or the one being
+    /// typed in as plain lines.
+    fn draw_long_text_pane(&self, motion_num: usize, input_mode: InputMode, input_str: &str) {
+        let mut screen_h = 0;
+        let mut screen_w = 0;
+        getmaxyx(stdscr(), &mut screen_h, &mut screen_w);
+        let x = screen_w / 2;
+        let width = max(screen_w - x - 4, 10) as usize;
+        let bottom = screen_h - 3;
+        let mut y = 6;
+
+        if input_mode == InputMode::Text
+            && motion_num <= self.columns.len()
+            && self.is_multi_line_field(motion_num)
+        {
+            let title = format!("[{}|{}]", motion_num, self.columns[motion_num - 1].name);
+            draw_editor(&title, input_str, y, x, width, (bottom - y) as usize);
+            return;
+        }
+
+        for col in 0..self.columns.len() {
+            let text = match &self.data[self.curr_row][col] {
+                Cell::Text(text) if self.columns[col].column_type == ColumnType::LongText => text,
+                _ => continue,
+            };
+            if y + 1 >= bottom {
+                break;
+            }
+            label(
+                &format!("[{}|{}]", col + 1, self.columns[col].name),
+                y,
+                x,
+                WHITE_PAIR,
+            );
+            y += markdown::draw(text, y + 1, x + 2, width, (bottom - y - 1) as usize) as i32 + 2;
+        }
+    }
+
+    /// Lists the options of `col` that could complete `input_str`, in the column's order.
+    fn draw_choices(&self, col: &Column, input_str: &str, y: i32) {
+        if !col.has_options() {
+            return;
+        }
+        let typed = match col.column_type {
+            ColumnType::Multiselect => input_str.rsplit(',').next().unwrap_or("").trim(),
+            _ => input_str.trim(),
+        };
+        let choices: Vec<&SelectOption> = col
+            .options
+            .iter()
+            .filter(|option| option.name.starts_with(typed))
+            .collect();
+        let width = choices
+            .iter()
+            .map(|option| option.name.len())
+            .max()
+            .unwrap_or(0);
+        for (choice_num, choice) in choices.iter().enumerate() {
+            label("|", y + choice_num as i32, 10, WHITE_PAIR);
+            attron(COLOR_PAIR(choice.color.pair()));
+            addstr(&fit_to_sizel(&choice.name, width, ' '));
+            attroff(COLOR_PAIR(choice.color.pair()));
+            addstr("|");
+        }
+    }
+
+    fn draw_column(
+        &self,
+        motion_num: usize,
+        input_mode: InputMode,
+        input_str: &str,
+        others: &OtherTables,
+    ) {
+        let start_y: usize = 8;
+        let col = &self.columns[self.curr_col];
+        label("[1|name]: ", start_y as i32, 8, WHITE_PAIR);
+        addstr(&col.name);
+        match input_mode {
+            InputMode::Text if motion_num == 1 => _ = addstr(&format!(" -> {}", input_str)),
+            _ => {}
+        };
+        label("[2|width]:  ", start_y as i32 + 1, 8, WHITE_PAIR);
+        addstr(&format!("{}", col.width));
+        match input_mode {
+            InputMode::Text if motion_num == 2 => _ = addstr(&format!(" -> {}", input_str)),
+            _ => {}
+        };
+        label("[3|type]: ", start_y as i32 + 2, 8, WHITE_PAIR);
+        addstr(&col.column_type.to_string());
+        match input_mode {
+            InputMode::Text if motion_num == 3 => _ = addstr(&format!(" -> {}", input_str)),
+            _ => {}
+        };
+        match (
+            &self.table_focus,
+            input_mode,
+            motion_num,
+            ColumnType::from_str(input_str),
+        ) {
+            // only types this ion knows can be changed to
+            (TableFocus::Column, InputMode::Text, 3, Ok(ColumnType::Unknown(_))) => {}
+            (TableFocus::Column, InputMode::Text, 3, Ok(new_type)) => {
+                self.draw_conversion_preview(&new_type, start_y as i32 + 7, others)
+            }
+            _ => {}
+        }
+        match col.column_type {
+            ColumnType::Number => {
+                label("format: ", start_y as i32 + 4, 8, WHITE_PAIR);
+                addstr(&col.number_format.to_string());
+            }
+            ColumnType::Date
+            | ColumnType::DateRange
+            | ColumnType::CreatedTime
+            | ColumnType::EditedTime => {
+                label("format: ", start_y as i32 + 4, 8, WHITE_PAIR);
+                addstr(col.date_format());
+            }
+            ColumnType::Relation => {
+                label("links to: ", start_y as i32 + 4, 8, WHITE_PAIR);
+                addstr(&col.relation);
+            }
+            ColumnType::Rollup => {
+                label("rollup: ", start_y as i32 + 4, 8, WHITE_PAIR);
+                if let Some(rollup) = &col.rollup {
+                    addstr(&format!(
+                        "{} of {} through {}",
+                        rollup.function, rollup.column, rollup.relation
+                    ));
+                }
+            }
+            ColumnType::Formula => {
+                label("[4|formula]: ", start_y as i32 + 3, 8, WHITE_PAIR);
+                addstr(&col.formula);
+                match input_mode {
+                    InputMode::Text if motion_num == 4 => _ = addstr(&format!(" -> {}", input_str)),
+                    _ => {}
+                };
+            }
+            _ => {}
+        }
+        if col.has_options() {
+            label("options: ", start_y as i32 + 4, 8, WHITE_PAIR);
+            let names: Vec<String> = col.options.iter().map(|o| o.name.clone()).collect();
+            draw_tags(&names, col, usize::MAX, false);
+        }
+        if !col.constraints.is_empty() {
+            label("constraints: ", start_y as i32 + 5, 8, WHITE_PAIR);
+            addstr(&col.constraints.to_string());
+        }
+        if !col.is_read_only() {
+            label("[4|default]: ", start_y as i32 + 3, 8, WHITE_PAIR);
+            addstr(&col.default);
+            match input_mode {
+                InputMode::Text if motion_num == 4 => _ = addstr(&format!(" -> {}", input_str)),
+                _ => {}
+            };
+        }
+    }
+
+    /// Lists the cells of the current column that would be lost by changing it to `new_type`,
+    /// so that can be looked at before pressing enter.
+    fn draw_conversion_preview(&self, new_type: &ColumnType, y: i32, others: &OtherTables) {
+        const MAX_SHOWN: usize = 10;
+        let losses = self.conversion_losses(self.curr_col, new_type, others);
+        if losses.is_empty() {
+            label(
+                &format!("every value converts to {}", new_type),
+                y,
+                8,
+                GREEN_PAIR,
+            );
+            return;
+        }
+        label(
+            &format!(
+                "{} value(s) won't fully convert to {}:",
+                losses.len(),
+                new_type
+            ),
+            y,
+            8,
+            RED_PAIR,
+        );
+        for (line, (row, old, converted)) in losses.iter().take(MAX_SHOWN).enumerate() {
+            let old = cell_as_col_type(old, &self.columns[self.curr_col]).0;
+            let becomes = match converted {
+                Cell::Empty => "cleared".to_string(),
+                Cell::Select(name) => format!("only {}", name),
+                _ => "invalid".to_string(),
+            };
+            label(
+                &format!("row {}: {} ({})", row + 1, old, becomes),
+                y + 1 + line as i32,
+                10,
+                BLUE_PAIR,
+            );
+        }
+        if losses.len() > MAX_SHOWN {
+            label(
+                &format!("and {} more", losses.len() - MAX_SHOWN),
+                y + 1 + MAX_SHOWN as i32,
+                10,
+                BLUE_PAIR,
+            );
+        }
+    }
+
+    fn to_new_elem_mode(&mut self) {
+        let id = self.new_row_id();
+        self.data.push(Row {
+            id,
+            cells: self.columns.iter().map(Column::default_cell).collect(),
+            ..Default::default()
+        });
+        self.stamp_row(
+            self.data.len() - 1,
+            &[ColumnType::CreatedTime, ColumnType::EditedTime],
+        );
+        self.table_focus = TableFocus::NewElement;
+    }
+
+    fn to_view_mode(&mut self) {
+        self.table_focus = TableFocus::View;
+    }
+
+    fn to_sort_mode(&mut self) {
+        self.table_focus = TableFocus::Sort;
+        if !self.shown_cols().contains(&self.curr_col) {
+            self.curr_col = self.shown_cols().first().copied().unwrap_or(0);
+        }
+    }
+
+    fn to_filter_mode(&mut self) {
+        self.table_focus = TableFocus::Filter;
+        self.curr_cond = min(self.curr_cond, self.num_conds().saturating_sub(1));
+        if !self.shown_cols().contains(&self.curr_col) {
+            self.curr_col = self.shown_cols().first().copied().unwrap_or(0);
+        }
+    }
+
+    fn to_table_mode(&mut self) {
+        self.table_focus = TableFocus::Table;
+    }
+
+    fn to_col_mode(&mut self) {
+        self.table_focus = TableFocus::Column;
+        self.curr_col = self.shown_cols().first().copied().unwrap_or(0);
     }
 
     fn to_new_col_mode(&mut self) {
@@ -404,273 +2637,1560 @@ impl Table {
             name: "".to_string(),
             width: 1,
             column_type: ColumnType::String,
+            ..Default::default()
         };
+        let default = new_col.default_cell();
         self.columns.push(new_col);
 
-        for row_num in 0..self.data.len() {
-            self.data[row_num].push("".to_string());
+        for row_num in 0..self.data.len() {
+            self.data[row_num].cells.push(default.clone());
+        }
+
+        // push new column
+        // add to each row in data
+        self.table_focus = TableFocus::NewColumn;
+    }
+
+    fn view_curr_elem(&mut self) {
+        self.table_focus = TableFocus::Element;
+    }
+
+    /// Whether the cursor is on a row, it isn't when the view shows none, on a board when the
+    /// current lane has no cards and on a calendar when the current row isn't on the current day.
+    fn has_curr_row(&self) -> bool {
+        if self.is_calendar() {
+            return self.day_rows(self.curr_day).contains(&self.curr_row);
+        }
+        if self.is_board() {
+            return self
+                .lanes()
+                .get(self.curr_lane)
+                .is_some_and(|lane| lane.rows.contains(&self.curr_row));
+        }
+        self.shown_rows.contains(&self.curr_row)
+    }
+
+    /// Where the current row is in the current view.
+    fn curr_line(&self) -> usize {
+        self.shown_rows
+            .iter()
+            .position(|row| *row == self.curr_row)
+            .unwrap_or(0)
+    }
+
+    fn up(&mut self, by: i32, def: i32) {
+        let amount: i32 = if by == 0 { def } else { by };
+        let line = self.curr_line().saturating_sub(amount as usize);
+        if let Some(row) = self.shown_rows.get(line) {
+            self.curr_row = *row;
+        }
+    }
+
+    fn down(&mut self, by: usize, def: usize) {
+        let amount: usize = if by == 0 { def } else { by };
+        let line = min(
+            self.curr_line() + amount,
+            self.shown_rows.len().saturating_sub(1),
+        );
+        if let Some(row) = self.shown_rows.get(line) {
+            self.curr_row = *row;
+        }
+    }
+
+    fn goto_row(&mut self, to: i32) {
+        if to > 0 && to <= self.shown_rows.len() as i32 {
+            self.curr_row = self.shown_rows[to as usize - 1];
+        }
+    }
+
+    fn switch_num_mode(&mut self) {
+        match self.num_mode {
+            NumMode::Absolute => self.num_mode = NumMode::Relative,
+            NumMode::Relative => self.num_mode = NumMode::Absolute,
+        }
+    }
+
+    fn prev_col(&mut self, by: i32) {
+        let cols = self.shown_cols();
+        let Some(pos) = cols.iter().position(|col| *col == self.curr_col) else {
+            self.curr_col = cols.first().copied().unwrap_or(self.curr_col);
+            return;
+        };
+        let amount: usize = if by == 0 { 1 } else { by as usize };
+        if pos >= amount {
+            self.curr_col = cols[pos - amount];
+        } else if amount == 1 {
+            self.curr_col = cols[cols.len() - 1];
+        } else {
+            self.curr_col = cols[0];
+        }
+    }
+
+    fn next_col(&mut self, by: usize) {
+        let cols = self.shown_cols();
+        let Some(pos) = cols.iter().position(|col| *col == self.curr_col) else {
+            self.curr_col = cols.first().copied().unwrap_or(self.curr_col);
+            return;
+        };
+        let amount: usize = if by == 0 { 1 } else { by };
+        if pos + amount < cols.len() {
+            self.curr_col = cols[pos + amount];
+        } else if amount == 1 {
+            self.curr_col = cols[0];
+        } else {
+            self.curr_col = cols[cols.len() - 1];
+        }
+    }
+
+    fn grow_curr_col(&mut self, motion_num: usize) {
+        let min_width = self.columns[self.curr_col].name.len() + 1;
+
+        let amount: i32 = if motion_num > 0 { motion_num as i32 } else { 1 };
+        self.columns[self.curr_col].width += amount;
+
+        if self.columns[self.curr_col].width < min_width as i32 {
+            self.columns[self.curr_col].width = min_width as i32;
+        }
+    }
+
+    fn shrink_curr_col(&mut self, motion_num: i32) {
+        let amount: i32 = if motion_num > 0 { motion_num } else { 1 };
+        if self.columns[self.curr_col].width - amount
+            > self.columns[self.curr_col].name.len() as i32
+        {
+            self.columns[self.curr_col].width -= amount;
+        } else {
+            self.columns[self.curr_col].width = self.columns[self.curr_col].name.len() as i32 + 1;
+        }
+    }
+
+    fn auto_size_col(&mut self, col: usize, others: &OtherTables) {
+        let new_size: i32 = match self.columns[col].column_type {
+            ColumnType::Boolean => {
+                const SIZE_OF_BOOL_IN_TABLE: i32 = 3;
+                max(
+                    SIZE_OF_BOOL_IN_TABLE,
+                    self.columns[col].name.len() as i32 + 1,
+                )
+            }
+            _ => {
+                let mut min_size = self.columns[col].name.len() + 1;
+                for row_num in 0..self.data.len() {
+                    let (str_to_display, _) = self.display_cell(row_num, col, others);
+                    min_size = max(min_size, str_to_display.len());
+                }
+
+                min_size as i32
+            }
+        };
+        self.columns[col].width = new_size;
+    }
+
+    fn auto_size_curr_col(&mut self, others: &OtherTables) {
+        self.auto_size_col(self.curr_col, others);
+    }
+
+    fn auto_size_cols(&mut self, others: &OtherTables) {
+        let num_cols = self.columns.len();
+        for col_num in 0..num_cols {
+            self.auto_size_col(col_num, others);
+        }
+    }
+
+    fn del_curr_col(&mut self) -> std::result::Result<(), String> {
+        let curr_col = self.curr_col;
+        let name = self.columns[curr_col].name.clone();
+        let dependent = self.columns.iter().enumerate().find_map(|(col_num, col)| {
+            (col_num != curr_col
+                && col.column_type == ColumnType::Formula
+                && col
+                    .parsed_formula()
+                    .as_ref()
+                    .is_ok_and(|expr| formula::columns(expr).contains(&name)))
+            .then_some(col)
+        });
+        if let Some(dependent) = dependent {
+            return Err(format!(
+                "Error: The formula of '{}' uses '{}'",
+                dependent.name, name
+            ));
+        }
+        for view in self.views.iter_mut() {
+            view.order.retain(|col| *col != name);
+            view.hidden.retain(|col| *col != name);
+            view.sorts.retain(|sort| sort.column != name);
+            for group in view.filter.iter_mut() {
+                group.retain(|cond| cond.column != name);
+            }
+            view.filter.retain(|group| !group.is_empty());
+            if view.group_by == name {
+                view.group_by.clear();
+                view.collapsed.clear();
+            }
+            if view.board_by == name {
+                view.board_by.clear();
+                if view.layout == Layout::Board {
+                    view.layout = Layout::Table;
+                }
+            }
+            if view.calendar_by == name {
+                view.calendar_by.clear();
+                if view.layout == Layout::Calendar {
+                    view.layout = Layout::Table;
+                }
+            }
+            view.card_fields.retain(|col| *col != name);
+            if view.query.uses(&name) {
+                view.query = Query::default();
+            }
+        }
+        if self.unsaved_query.uses(&name) {
+            self.unsaved_query = Query::default();
+        }
+        self.columns.remove(curr_col);
+        for row_num in 0..self.data.len() {
+            self.data[row_num].cells.remove(curr_col);
+        }
+        Ok(())
+    }
+
+    /// Swaps the current column with the shown one `by` places away in the current view's order.
+    fn move_curr_col(&mut self, by: isize) {
+        let shown = self.shown_cols();
+        let other = shown
+            .iter()
+            .position(|col| *col == self.curr_col)
+            .and_then(|pos| pos.checked_add_signed(by))
+            .and_then(|pos| shown.get(pos));
+        let Some(&other) = other else {
+            return;
+        };
+        let mut order = self.col_order();
+        let from = order.iter().position(|col| *col == self.curr_col).unwrap();
+        let to = order.iter().position(|col| *col == other).unwrap();
+        order.swap(from, to);
+        self.views[self.curr_view].order = order
+            .into_iter()
+            .map(|col| self.columns[col].name.clone())
+            .collect();
+    }
+
+    fn move_curr_col_left(&mut self) {
+        self.move_curr_col(-1);
+    }
+
+    fn move_curr_col_right(&mut self) {
+        self.move_curr_col(1);
+    }
+
+    // fn move_curr_row_up(&mut self) {
+    //     if self.curr_row <= 0 {
+    //         return;
+    //     }
+
+    //     let x: Vec<String> = self.data[self.curr_row - 1]
+    //         .iter()
+    //         .map(|s| s.clone())
+    //         .collect();
+    //     let y: Vec<String> = self.data[self.curr_row].iter().map(|s| s.clone()).collect();
+    //     self.data.push(x);
+    //     self.data.push(y);
+    //     self.data.swap_remove(self.curr_row - 1);
+    //     self.data.swap_remove(self.curr_row);
+
+    //     self.curr_row -= 1;
+    // }
+
+    // fn move_curr_row_down(&mut self) {
+    //     if self.curr_row + 1 >= self.data.len() {
+    //         return;
+    //     }
+
+    //     let x: Vec<String> = self.data[self.curr_row].iter().map(|s| s.clone()).collect();
+    //     let y: Vec<String> = self.data[self.curr_row + 1]
+    //         .iter()
+    //         .map(|s| s.clone())
+    //         .collect();
+    //     self.data.push(x);
+    //     self.data.push(y);
+    //     self.data.swap_remove(self.curr_row);
+    //     self.data.swap_remove(self.curr_row + 1);
+
+    //     self.curr_row += 1;
+    // }
+
+    fn view(&self) -> &View {
+        &self.views[self.curr_view]
+    }
+
+    /// Every column in the current view's order, shown or not.
+    fn col_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = vec![];
+        let named = self
+            .view()
+            .order
+            .iter()
+            .filter_map(|name| self.col_num(name));
+        for col in named.chain(0..self.columns.len()) {
+            if !order.contains(&col) {
+                order.push(col);
+            }
         }
 
-        // push new column
-        // add to each row in data
-        self.table_focus = TableFocus::NewColumn;
+        order
     }
 
-    fn view_curr_elem(&mut self) {
-        self.table_focus = TableFocus::Element;
+    /// The columns the current view shows, in the order it shows them.
+    fn shown_cols(&self) -> Vec<usize> {
+        self.col_order()
+            .into_iter()
+            .filter(|col| !self.view().hidden.contains(&self.columns[*col].name))
+            .collect()
     }
 
-    fn up(&mut self, by: i32, def: i32) {
-        let amount: i32 = if by == 0 { def } else { by };
-        if self.curr_row as i32 - amount >= 0 {
-            self.curr_row -= amount as usize;
+    /// Works out which rows the current view shows, called before drawing so it keeps up with
+    /// changes to the rows.
+    fn refresh_view(&mut self, others: &OtherTables) {
+        let queries: Vec<&formula::Expr> = [&self.view().query, &self.unsaved_query]
+            .into_iter()
+            .filter_map(|query| query.expr.as_ref())
+            .collect();
+        let mut rows: Vec<usize> = (0..self.data.len())
+            .filter(|row| self.passes_filter(*row, &queries, others))
+            .collect();
+        rows.sort_by(|a, b| self.cmp_rows(*a, *b, others));
+        self.groups = self.group_rows(&rows, others);
+        self.shown_rows = if self.groups.is_empty() {
+            rows
         } else {
-            self.curr_row = 0;
+            self.groups
+                .iter()
+                .filter(|group| !group.collapsed)
+                .flat_map(|group| group.rows.iter().copied())
+                .collect()
+        };
+        // a row that's being typed in is kept until it's done
+        let keeps_row = matches!(
+            self.table_focus,
+            TableFocus::Element | TableFocus::NewElement
+        );
+        if !keeps_row && !self.shown_rows.contains(&self.curr_row) {
+            self.curr_row = self.shown_rows.first().copied().unwrap_or(0);
+        }
+        // the cursor can be in an empty lane, otherwise it's on a card in its lane
+        let lanes = self.lanes();
+        let stays = lanes
+            .get(self.curr_lane)
+            .is_some_and(|lane| lane.rows.is_empty() || lane.rows.contains(&self.curr_row));
+        if !stays {
+            self.curr_lane = lanes
+                .iter()
+                .position(|lane| lane.rows.contains(&self.curr_row))
+                .unwrap_or(0);
         }
     }
 
-    fn down(&mut self, by: usize, def: usize) {
-        let amount: usize = if by == 0 { def } else { by };
-        if self.curr_row + amount < self.data.len() {
-            self.curr_row += amount;
+    /// Splits sorted `rows` into groups by the current view's group column, in the order of its
+    /// values, backwards when the view sorts that column descending.
+    fn group_rows(&self, rows: &[usize], others: &OtherTables) -> Vec<Group> {
+        let Some(col) = self.col_num(&self.view().group_by) else {
+            return vec![];
+        };
+        // unknown layouts are drawn as tables
+        if matches!(self.view().layout, Layout::Board | Layout::Calendar) {
+            return vec![];
+        }
+        let column = &self.columns[col];
+        let mut groups: Vec<(Cell, Group)> = vec![];
+        for &row in rows {
+            let value = match (&column.column_type, self.compared_value(row, col, others)) {
+                (ColumnType::Boolean, Cell::Empty) => Cell::Boolean(false),
+                (_, value) => value,
+            };
+            let key = value.to_string();
+            match groups.iter_mut().find(|(_, group)| group.key == key) {
+                Some((_, group)) => group.rows.push(row),
+                None => {
+                    let name = match value {
+                        Cell::Empty => format!("No {}", column.name),
+                        _ => self.display_cell(row, col, others).0,
+                    };
+                    let group = Group {
+                        collapsed: self.view().collapsed.contains(&key),
+                        key,
+                        name,
+                        rows: vec![row],
+                    };
+                    groups.push((value, group));
+                }
+            }
+        }
+        let descending = self
+            .view()
+            .sorts
+            .iter()
+            .any(|sort| sort.column == column.name && sort.descending);
+        groups.sort_by(|(a, _), (b, _)| {
+            a.sort_rank().cmp(&b.sort_rank()).then_with(|| {
+                let ordering = column.cmp_values(a, b);
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            })
+        });
+
+        groups.into_iter().map(|(_, group)| group).collect()
+    }
+
+    /// The lanes of the current view's board, one for each option of its column in order, then
+    /// one for rows with none. A row with many options is in each of their lanes.
+    fn lanes(&self) -> Vec<Lane> {
+        let Some(col) = self.col_num(&self.view().board_by) else {
+            return vec![];
+        };
+        let mut lanes: Vec<Lane> = self.columns[col]
+            .options
+            .iter()
+            .map(|option| Lane {
+                option: Some(option.name.clone()),
+                rows: vec![],
+            })
+            .collect();
+        lanes.push(Lane {
+            option: None,
+            rows: vec![],
+        });
+        for &row in self.shown_rows.iter() {
+            let names = self.data[row][col].option_names();
+            for lane in lanes.iter_mut() {
+                let in_lane = match &lane.option {
+                    Some(option) => names.contains(option),
+                    None => names.is_empty(),
+                };
+                if in_lane {
+                    lane.rows.push(row);
+                }
+            }
+        }
+
+        lanes
+    }
+
+    fn is_board(&self) -> bool {
+        self.view().layout == Layout::Board && self.col_num(&self.view().board_by).is_some()
+    }
+
+    /// Moves the cursor `by` cards down its lane, or up when negative.
+    fn move_in_lane(&mut self, by: isize) {
+        let lanes = self.lanes();
+        let Some(lane) = lanes.get(self.curr_lane) else {
+            return;
+        };
+        let pos = lane.rows.iter().position(|row| *row == self.curr_row);
+        let to = match pos {
+            Some(pos) => pos
+                .saturating_add_signed(by)
+                .min(lane.rows.len().saturating_sub(1)),
+            None => 0,
+        };
+        if let Some(row) = lane.rows.get(to) {
+            self.curr_row = *row;
+        }
+    }
+
+    /// Moves the cursor `by` lanes right, or left when negative, onto the card as far down.
+    fn move_lane(&mut self, by: isize) {
+        let lanes = self.lanes();
+        if lanes.is_empty() {
+            return;
+        }
+        let pos = lanes
+            .get(self.curr_lane)
+            .and_then(|lane| lane.rows.iter().position(|row| *row == self.curr_row))
+            .unwrap_or(0);
+        self.curr_lane = self
+            .curr_lane
+            .saturating_add_signed(by)
+            .min(lanes.len() - 1);
+        let rows = &lanes[self.curr_lane].rows;
+        if let Some(row) = rows.get(pos).or(rows.last()) {
+            self.curr_row = *row;
+        }
+    }
+
+    /// Moves the current card `by` lanes right, or left when negative, by changing its option.
+    fn move_card(&mut self, by: isize) -> std::result::Result<(), String> {
+        let lanes = self.lanes();
+        let (Some(col), Some(lane)) = (
+            self.col_num(&self.view().board_by),
+            lanes.get(self.curr_lane),
+        ) else {
+            return Ok(());
+        };
+        if !lane.rows.contains(&self.curr_row) {
+            return Ok(());
+        }
+        let to = self
+            .curr_lane
+            .saturating_add_signed(by)
+            .min(lanes.len() - 1);
+        if to == self.curr_lane {
+            return Ok(());
+        }
+        let column = &self.columns[col];
+        let cell = match (&column.column_type, &lanes[to].option) {
+            (_, None) => Cell::Empty,
+            (ColumnType::Multiselect, Some(option)) => {
+                let mut options: Vec<String> = self.data[self.curr_row][col]
+                    .option_names()
+                    .iter()
+                    .filter(|name| Some(*name) != lane.option.as_ref())
+                    .cloned()
+                    .collect();
+                if !options.contains(option) {
+                    options.push(option.clone());
+                }
+                Cell::Options(options)
+            }
+            (_, Some(option)) => Cell::Select(option.clone()),
+        };
+        column.check_constraints(&cell)?;
+        self.set_cell(self.curr_row, col, cell);
+        self.curr_lane = to;
+
+        Ok(())
+    }
+
+    fn is_calendar(&self) -> bool {
+        self.view().layout == Layout::Calendar && self.col_num(&self.view().calendar_by).is_some()
+    }
+
+    /// The shown rows on `date` by the current view's calendar column, in the view's order.
+    fn day_rows(&self, date: NaiveDate) -> Vec<usize> {
+        let Some(col) = self.col_num(&self.view().calendar_by) else {
+            return vec![];
+        };
+        self.shown_rows
+            .iter()
+            .copied()
+            .filter(|row| {
+                self.data[*row][col]
+                    .days()
+                    .is_some_and(|(start, end)| start <= date && date <= end)
+            })
+            .collect()
+    }
+
+    /// Moves the calendar's cursor to `date`, onto the first row on it. On a day without rows
+    /// there's no current row.
+    fn goto_day(&mut self, date: Option<NaiveDate>) {
+        let Some(date) = date else {
+            return;
+        };
+        self.curr_day = date;
+        if let Some(row) = self.day_rows(date).first() {
+            self.curr_row = *row;
+        }
+    }
+
+    /// Moves the calendar's cursor `by` days forward, or back when negative.
+    fn move_day(&mut self, by: i64) {
+        let days = Days::new(by.unsigned_abs());
+        let date = if by < 0 {
+            self.curr_day.checked_sub_days(days)
+        } else {
+            self.curr_day.checked_add_days(days)
+        };
+        self.goto_day(date);
+    }
+
+    /// Moves the calendar's cursor `by` months forward, or back when negative, to the same day or
+    /// the last of a shorter month.
+    fn move_month(&mut self, by: i32) {
+        let months = Months::new(by.unsigned_abs());
+        let date = if by < 0 {
+            self.curr_day.checked_sub_months(months)
         } else {
-            self.curr_row = self.data.len() - 1;
+            self.curr_day.checked_add_months(months)
+        };
+        self.goto_day(date);
+    }
+
+    /// Opens the `row_num`th row on the calendar's current day, or the first without one.
+    fn view_day_elem(&mut self, row_num: usize) -> std::result::Result<(), String> {
+        let rows = self.day_rows(self.curr_day);
+        match rows.get(max(row_num, 1) - 1) {
+            Some(row) => {
+                self.curr_row = *row;
+                self.view_curr_elem();
+                Ok(())
+            }
+            None if rows.is_empty() => Err(format!(
+                "Error: There's nothing on {}",
+                self.curr_day.format(DATE_FORMAT)
+            )),
+            None => Err(format!(
+                "Error: There are only {} entries on {}",
+                rows.len(),
+                self.curr_day.format(DATE_FORMAT)
+            )),
         }
     }
 
-    fn goto_row(&mut self, to: i32) {
-        if to > 0 && to <= self.data.len() as i32 {
-            self.curr_row = to as usize - 1;
+    /// How many lines the rows take in the table, with the lines naming their groups.
+    fn num_lines(&self) -> usize {
+        self.shown_rows.len() + self.groups.len()
+    }
+
+    /// Hides the rows of the group `group_num`, or shows them when they're hidden.
+    fn toggle_group(&mut self, group_num: usize) {
+        let Some(key) = self.groups.get(group_num).map(|group| group.key.clone()) else {
+            return;
+        };
+        let collapsed = &mut self.views[self.curr_view].collapsed;
+        match collapsed.iter().position(|collapsed| *collapsed == key) {
+            Some(pos) => _ = collapsed.remove(pos),
+            None => collapsed.push(key),
         }
     }
 
-    fn switch_num_mode(&mut self) {
-        match self.num_mode {
-            NumMode::Absolute => self.num_mode = NumMode::Relative,
-            NumMode::Relative => self.num_mode = NumMode::Absolute,
+    /// Hides the rows of the current row's group, or with `group_num` that group's.
+    fn toggle_curr_group(&mut self, group_num: usize) {
+        let group_num = match group_num {
+            0 => self
+                .groups
+                .iter()
+                .position(|group| group.rows.contains(&self.curr_row)),
+            _ => Some(group_num - 1),
+        };
+        if let Some(group_num) = group_num {
+            self.toggle_group(group_num);
         }
     }
 
-    fn prev_col(&mut self, by: i32) {
-        let amount: i32 = if by == 0 { 1 } else { by };
-        if self.curr_col as i32 - amount >= 0 {
-            self.curr_col -= amount as usize;
-        } else if amount == 1 {
-            self.curr_col = self.columns.len() - 1;
+    /// Shows every group when any is hidden, otherwise hides them all. Values no row has anymore
+    /// are forgotten either way.
+    fn toggle_groups(&mut self) {
+        let any_collapsed = self.groups.iter().any(|group| group.collapsed);
+        let keys: Vec<String> = self.groups.iter().map(|group| group.key.clone()).collect();
+        let collapsed = &mut self.views[self.curr_view].collapsed;
+        if any_collapsed {
+            collapsed.clear();
         } else {
-            self.curr_col = 0;
+            *collapsed = keys;
         }
     }
 
-    fn next_col(&mut self, by: usize) {
+    /// The value of a cell as sorts and filters see it, links are the titles they're shown as.
+    fn compared_value(&self, row: usize, col: usize, others: &OtherTables) -> Cell {
+        match (&self.columns[col].column_type, self.value(row, col, others)) {
+            (ColumnType::Relation, Cell::Relation(_)) => {
+                Cell::Text(self.display_cell(row, col, others).0)
+            }
+            (_, value) => value,
+        }
+    }
+
+    /// Whether `row` passes the current view's filter and is true for every query, conditions on
+    /// columns that are gone pass and queries that can't be worked out for the row fail.
+    fn passes_filter(&self, row: usize, queries: &[&formula::Expr], others: &OtherTables) -> bool {
+        let filter = &self.view().filter;
+        let lookup = |name: &str| {
+            self.col_num(name)
+                .map(|col| self.compared_value(row, col, others))
+        };
+        let passes_queries = queries.iter().all(|query| {
+            formula::eval(query, &lookup).is_ok_and(|value| formula::is_truthy(&value))
+        });
+        passes_queries
+            && (filter.is_empty()
+                || filter.iter().any(|group| {
+                    group
+                        .iter()
+                        .all(|cond| self.passes_condition(row, cond, others))
+                }))
+    }
+
+    /// Reads `query` as a formula over columns the table has.
+    fn parse_query(&self, query: &str) -> std::result::Result<Query, String> {
+        let expr = formula::parse(query)?;
+        match formula::columns(&expr)
+            .into_iter()
+            .find(|name| self.col_num(name).is_none())
+        {
+            Some(name) => Err(format!("there is no column '{}'", name)),
+            None => Ok(Query {
+                text: query.to_string(),
+                expr: Some(expr),
+            }),
+        }
+    }
+
+    fn is_filtered(&self) -> bool {
+        !self.view().filter.is_empty()
+            || !self.view().query.is_empty()
+            || !self.unsaved_query.is_empty()
+    }
+
+    fn passes_condition(&self, row: usize, cond: &Condition, others: &OtherTables) -> bool {
+        let Some(col) = self.col_num(&cond.column) else {
+            return true;
+        };
+        let column = &self.columns[col];
+        let value = self.compared_value(row, col, others);
+        let target = column.filter_target(&cond.value);
+        let value_text = value.to_string().to_lowercase();
+        let raw = cond.value.to_lowercase();
+        let contains = match &value {
+            Cell::Options(options) => options.iter().any(|option| option.to_lowercase() == raw),
+            _ => {
+                value_text.contains(&raw)
+                    || self
+                        .display_cell(row, col, others)
+                        .0
+                        .to_lowercase()
+                        .contains(&raw)
+            }
+        };
+        let ordering = formula::compare(&value, &target);
+        let is = match ordering {
+            Some(ordering) => ordering.is_eq(),
+            None => value_text == raw,
+        };
+        match cond.op {
+            _ if cond.op.takes_value() && cond.value.is_empty() => true,
+            FilterOp::Contains => contains,
+            FilterOp::NotContains => !contains,
+            FilterOp::Is => is,
+            FilterOp::IsNot => !is,
+            FilterOp::Less | FilterOp::Before => ordering.is_some_and(Ordering::is_lt),
+            FilterOp::LessOrEqual => ordering.is_some_and(Ordering::is_le),
+            FilterOp::Greater | FilterOp::After => ordering.is_some_and(Ordering::is_gt),
+            FilterOp::GreaterOrEqual => ordering.is_some_and(Ordering::is_ge),
+            FilterOp::Within => {
+                let today = Local::now().date_naive();
+                let days = cond.value.trim().parse::<u32>().unwrap_or(0);
+                match today.checked_add_days(Days::new(days as u64)) {
+                    Some(last) => {
+                        formula::compare(&value, &Cell::Date(today)).is_some_and(Ordering::is_ge)
+                            && formula::compare(&value, &Cell::Date(last))
+                                .is_some_and(Ordering::is_le)
+                    }
+                    None => false,
+                }
+            }
+            FilterOp::Checked => value == Cell::Boolean(true),
+            FilterOp::Unchecked => value != Cell::Boolean(true),
+            FilterOp::Empty => value == Cell::Empty,
+            FilterOp::NotEmpty => value != Cell::Empty,
+            FilterOp::Unknown(_) => true,
+        }
+    }
+
+    /// The group and place in it of the `cond`th condition of the current view's filter.
+    fn cond_pos(&self, cond: usize) -> Option<(usize, usize)> {
+        let mut cond = cond;
+        for (group_num, group) in self.view().filter.iter().enumerate() {
+            if cond < group.len() {
+                return Some((group_num, cond));
+            }
+            cond -= group.len();
+        }
+
+        None
+    }
+
+    fn num_conds(&self) -> usize {
+        self.view().filter.iter().map(Vec::len).sum()
+    }
+
+    /// Adds a condition on the current column to the current condition's group, or in a new
+    /// group when `or` is set, and makes it the current one.
+    fn add_cond(&mut self, or: bool) {
+        let column = &self.columns[self.curr_col];
+        let cond = Condition {
+            column: column.name.clone(),
+            op: column.filter_ops()[0].clone(),
+            value: "".to_string(),
+            extra: Map::new(),
+        };
+        let group = match self.cond_pos(self.curr_cond) {
+            Some((group, _)) if !or => group,
+            _ => {
+                self.views[self.curr_view].filter.push(vec![]);
+                self.view().filter.len() - 1
+            }
+        };
+        let filter = &mut self.views[self.curr_view].filter;
+        filter[group].push(cond);
+        self.curr_cond = filter[..=group].iter().map(Vec::len).sum::<usize>() - 1;
+    }
+
+    fn del_curr_cond(&mut self) {
+        let Some((group, pos)) = self.cond_pos(self.curr_cond) else {
+            return;
+        };
+        let filter = &mut self.views[self.curr_view].filter;
+        filter[group].remove(pos);
+        if filter[group].is_empty() {
+            filter.remove(group);
+        }
+        self.curr_cond = min(self.curr_cond, self.num_conds().saturating_sub(1));
+    }
+
+    /// Sets field `field` of the current condition, its column, test or value, to `raw`.
+    fn set_cond_field(&mut self, field: usize, raw: &str) -> std::result::Result<(), String> {
+        let Some((group, pos)) = self.cond_pos(self.curr_cond) else {
+            return Err("Error: There is no condition".to_string());
+        };
+        let mut cond = self.view().filter[group][pos].clone();
+        let raw = raw.trim();
+        let col = match self.col_num(if field == 1 { raw } else { &cond.column }) {
+            Some(col) => col,
+            None => return Err(format!("Error: There is no column '{}'", raw)),
+        };
+        let column = &self.columns[col];
+        let ops = column.filter_ops();
+        match field {
+            1 => {
+                cond.column = column.name.clone();
+                if !ops.contains(&cond.op) {
+                    cond.op = ops[0].clone();
+                }
+            }
+            2 => match FilterOp::from_str(raw) {
+                Ok(op) if ops.contains(&op) => cond.op = op,
+                _ => return Err(format!("Error: '{}' can't test '{}'", raw, column.name)),
+            },
+            _ => {
+                column.check_filter_value(&cond.op, raw)?;
+                cond.value = raw.to_string();
+            }
+        }
+        self.views[self.curr_view].filter[group][pos] = cond;
+
+        Ok(())
+    }
+
+    /// Orders two rows by the current view's sort keys, empty and invalid cells go last whichever
+    /// way a column is sorted.
+    fn cmp_rows(&self, a: usize, b: usize, others: &OtherTables) -> Ordering {
+        for sort in self.view().sorts.iter() {
+            let Some(col) = self.col_num(&sort.column) else {
+                continue;
+            };
+            let (a, b) = (
+                self.compared_value(a, col, others),
+                self.compared_value(b, col, others),
+            );
+            let ordering = a.sort_rank().cmp(&b.sort_rank()).then_with(|| {
+                let ordering = self.columns[col].cmp_values(&a, &b);
+                if sort.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+            if ordering.is_ne() {
+                return ordering;
+            }
+        }
+
+        Ordering::Equal
+    }
+
+    /// Sorts the current view by the current column, after the keys it's already sorted by.
+    fn sort_curr_col(&mut self, descending: bool) {
+        let name = self.columns[self.curr_col].name.clone();
+        let sorts = &mut self.views[self.curr_view].sorts;
+        match sorts.iter_mut().find(|sort| sort.column == name) {
+            Some(sort) => sort.descending = descending,
+            None => sorts.push(Sort {
+                column: name,
+                descending,
+                extra: Map::new(),
+            }),
+        }
+    }
+
+    fn unsort_curr_col(&mut self) {
+        let name = &self.columns[self.curr_col].name;
+        self.views[self.curr_view]
+            .sorts
+            .retain(|sort| sort.column != *name);
+    }
+
+    /// Moves the current column's sort key `by` places towards the most important one.
+    fn move_curr_sort(&mut self, by: isize) {
+        let name = &self.columns[self.curr_col].name;
+        let sorts = &mut self.views[self.curr_view].sorts;
+        let Some(from) = sorts.iter().position(|sort| sort.column == *name) else {
+            return;
+        };
+        let to = from.saturating_add_signed(-by).min(sorts.len() - 1);
+        let sort = sorts.remove(from);
+        sorts.insert(to, sort);
+    }
+
+    fn view_num(&self, name: &str) -> Option<usize> {
+        self.views.iter().position(|view| view.name == name)
+    }
+
+    fn new_view(&mut self, name: &str) -> std::result::Result<(), String> {
+        if name.is_empty() {
+            return Err("Error: A view needs a name".to_string());
+        }
+        if self.view_num(name).is_some() {
+            return Err(format!("Error: View '{}' already exists", name));
+        }
+        self.views.push(View::named(name));
+        self.curr_view = self.views.len() - 1;
+
+        Ok(())
+    }
+
+    fn rename_view(&mut self, name: &str) -> std::result::Result<(), String> {
+        if name.is_empty() {
+            return Err("Error: A view needs a name".to_string());
+        }
+        match self.view_num(name) {
+            Some(view_num) if view_num != self.curr_view => {
+                Err(format!("Error: View '{}' already exists", name))
+            }
+            _ => {
+                self.views[self.curr_view].name = name.to_string();
+                Ok(())
+            }
+        }
+    }
+
+    fn del_view(&mut self) -> std::result::Result<(), String> {
+        if self.views.len() == 1 {
+            return Err("Error: The only view can't be deleted".to_string());
+        }
+        self.views.remove(self.curr_view);
+        self.curr_view = self.curr_view.saturating_sub(1);
+
+        Ok(())
+    }
+
+    fn prev_view(&mut self, by: usize) {
         let amount: usize = if by == 0 { 1 } else { by };
-        if self.curr_col + amount < self.columns.len() {
-            self.curr_col += amount;
-        } else if amount == 1 {
-            self.curr_col = 0;
-        } else {
-            self.curr_col = self.columns.len() - 1;
+        let len = self.views.len();
+        self.curr_view = (self.curr_view + len - amount % len) % len;
+    }
+
+    fn next_view(&mut self, by: usize) {
+        let amount: usize = if by == 0 { 1 } else { by };
+        self.curr_view = (self.curr_view + amount) % self.views.len();
+    }
+
+    fn goto_view(&mut self, to: usize) {
+        if to > 0 && to <= self.views.len() {
+            self.curr_view = to - 1;
         }
     }
 
-    fn grow_curr_col(&mut self, motion_num: usize) {
-        let min_width = self.columns[self.curr_col].name.len() + 1;
+    fn hide_col(&mut self, col: usize) {
+        let name = self.columns[col].name.clone();
+        let hidden = &mut self.views[self.curr_view].hidden;
+        if !hidden.contains(&name) {
+            hidden.push(name);
+        }
+    }
 
-        let amount: i32 = if motion_num > 0 { motion_num as i32 } else { 1 };
-        self.columns[self.curr_col].width += amount;
+    fn show_col(&mut self, col: usize) {
+        let name = &self.columns[col].name;
+        self.views[self.curr_view]
+            .hidden
+            .retain(|hidden| hidden != name);
+    }
 
-        if self.columns[self.curr_col].width < min_width as i32 {
-            self.columns[self.curr_col].width = min_width as i32;
+    /// Renames a column, along with the places views name it.
+    /// Column names have to be there and be unique, they're what views and formulas use.
+    fn check_col_name(&self, col: usize, name: &str) -> std::result::Result<(), String> {
+        if name.trim().is_empty() {
+            return Err("Error: A column needs a name".to_string());
         }
+        let taken = self
+            .columns
+            .iter()
+            .enumerate()
+            .any(|(col_num, column)| col_num != col && column.name == name);
+        if taken {
+            return Err(format!("Error: Column '{}' already exists", name));
+        }
+        Ok(())
     }
 
-    fn shrink_curr_col(&mut self, motion_num: i32) {
-        let amount: i32 = if motion_num > 0 { motion_num } else { 1 };
-        if self.columns[self.curr_col].width - amount
-            > self.columns[self.curr_col].name.len() as i32
+    fn rename_col(&mut self, col: usize, name: &str) -> std::result::Result<(), String> {
+        self.check_col_name(col, name)?;
+        let old_name = std::mem::replace(&mut self.columns[col].name, name.to_string());
+        for column in self.columns.iter_mut() {
+            let renamed = formula::rename_column(&column.formula, &old_name, name);
+            if renamed != column.formula {
+                column.set_formula(&renamed);
+            }
+        }
+        for rollup in self
+            .columns
+            .iter_mut()
+            .filter_map(|col| col.rollup.as_mut())
         {
-            self.columns[self.curr_col].width -= amount;
+            if rollup.relation == old_name {
+                rollup.relation = name.to_string();
+            }
+        }
+        self.unsaved_query.rename_column(&old_name, name);
+        for view in self.views.iter_mut() {
+            view.query.rename_column(&old_name, name);
+            let sorted = view.sorts.iter_mut().map(|sort| &mut sort.column);
+            let filtered = view
+                .filter
+                .iter_mut()
+                .flatten()
+                .map(|cond| &mut cond.column);
+            for view_col in view
+                .order
+                .iter_mut()
+                .chain(view.hidden.iter_mut())
+                .chain(sorted)
+                .chain(filtered)
+                .chain(view.card_fields.iter_mut())
+                .chain([
+                    &mut view.group_by,
+                    &mut view.board_by,
+                    &mut view.calendar_by,
+                ])
+            {
+                if *view_col == old_name {
+                    *view_col = name.to_string();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn new_row_id(&mut self) -> RowId {
+        self.next_row_id = max(self.next_row_id, 1);
+        let id = self.next_row_id;
+        self.next_row_id += 1;
+
+        id
+    }
+
+    fn row_num(&self, id: RowId) -> Option<usize> {
+        self.data.iter().position(|row| row.id == id)
+    }
+
+    /// What a row is called when other tables link to it, its first string column.
+    fn row_title(&self, row_num: usize) -> String {
+        let title_col = self
+            .columns
+            .iter()
+            .position(|col| matches!(col.column_type, ColumnType::String))
+            .unwrap_or(0);
+        match self.data[row_num].cells.get(title_col) {
+            Some(Cell::Empty) | None => format!("#{}", row_num + 1),
+            Some(cell) => cell_as_col_type(cell, &self.columns[title_col]).0,
+        }
+    }
+
+    /// Opens a link or contact with the system opener, from the `field`-th column or from the
+    /// first column that has one when `field` is 0.
+    fn open_cell(&self, row: usize, field: usize) -> std::result::Result<String, String> {
+        if row >= self.data.len() {
+            return Err("Error: There is no row to open".to_string());
+        }
+        let col = match field {
+            0 => (0..self.columns.len())
+                .find(|col| {
+                    open_target(&self.data[row][*col], &self.columns[*col].column_type).is_some()
+                })
+                .ok_or("Error: This row has nothing to open")?,
+            field if field <= self.columns.len() => field - 1,
+            _ => return Err(format!("Error: There is no field {}", field)),
+        };
+        let target = open_target(&self.data[row][col], &self.columns[col].column_type)
+            .ok_or_else(|| format!("Error: '{}' has nothing to open", self.columns[col].name))?;
+        let mut child = std::process::Command::new("xdg-open")
+            .arg(&target)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .map_err(|error| format!("Error: Couldn't run xdg-open: {}", error))?;
+        std::thread::spawn(move || child.wait());
+
+        Ok(target)
+    }
+
+    fn linked_table<'a>(&'a self, path: &str, others: &'a OtherTables) -> Option<&'a Table> {
+        if path == self.path {
+            Some(self)
         } else {
-            self.columns[self.curr_col].width = self.columns[self.curr_col].name.len() as i32 + 1;
+            others.get(path)
         }
     }
 
-    fn auto_size_col(&mut self, col: usize) {
-        let new_size: i32 = match self.columns[col].column_type {
-            ColumnType::Boolean => {
-                const SIZE_OF_BOOL_IN_TABLE: i32 = 3;
-                max(
-                    SIZE_OF_BOOL_IN_TABLE,
-                    self.columns[col].name.len() as i32 + 1,
-                )
-            }
-            _ => {
-                let mut min_size = self.columns[col].name.len() + 1;
-                for row in self.data.iter() {
-                    min_size = max(min_size, row[col].len());
+    /// Reads the rows a relation links to from their titles, or `#3` for the 3rd row.
+    fn parse_links(&self, raw: &str) -> std::result::Result<Cell, String> {
+        if raw.trim().is_empty() {
+            return Ok(Cell::Empty);
+        }
+        let mut ids: Vec<RowId> = vec![];
+        for link in raw.split(',').map(|link| link.trim()) {
+            let row_num = match link.strip_prefix('#').map(|num| num.parse::<usize>()) {
+                Some(Ok(num)) if num > 0 && num <= self.data.len() => Some(num - 1),
+                _ => (0..self.data.len()).find(|row_num| self.row_title(*row_num) == link),
+            };
+            match row_num {
+                Some(row_num) => ids.push(self.data[row_num].id),
+                None => {
+                    return Err(format!(
+                        "Error: There is no row '{}' in '{}'",
+                        link, self.path
+                    ))
                 }
+            }
+        }
 
-                min_size as i32
+        Ok(Cell::Relation(ids))
+    }
+
+    /// Reads what the user typed in for a cell, or why it can't go there.
+    fn parse_input(
+        &self,
+        row: usize,
+        col: usize,
+        input_str: &str,
+        others: &OtherTables,
+    ) -> std::result::Result<Cell, String> {
+        let column = &self.columns[col];
+        if column.is_read_only() {
+            return Err(format!(
+                "Error: Column '{}' is read-only and can't be edited",
+                column.name
+            ));
+        }
+        let cell = match column.column_type {
+            ColumnType::Boolean if input_str.is_empty() => {
+                Cell::Boolean(self.data[row][col] != Cell::Boolean(true))
             }
+            ColumnType::Relation => match self.linked_table(&column.relation, others) {
+                Some(linked) => linked.parse_links(input_str)?,
+                None => {
+                    return Err(format!(
+                        "Error: Column '{}' doesn't link to a table",
+                        column.name
+                    ))
+                }
+            },
+            _ => Cell::parse(input_str, column),
         };
-        self.columns[col].width = new_size;
+        self.check_cell(row, col, cell)
     }
 
-    fn auto_size_curr_col(&mut self) {
-        self.auto_size_col(self.curr_col);
+    /// Checks `cell` can go in column `col` of `row`, with its type, constraints and uniqueness.
+    fn check_cell(&self, row: usize, col: usize, cell: Cell) -> std::result::Result<Cell, String> {
+        let column = &self.columns[col];
+        column.validate(&cell)?;
+        column.check_constraints(&cell)?;
+        if column.constraints.unique
+            && cell != Cell::Empty
+            && (0..self.data.len()).any(|other| other != row && self.data[other][col] == cell)
+        {
+            return Err(format!(
+                "Error: '{}' is already in '{}'",
+                cell_as_col_type(&cell, column).0,
+                column.name
+            ));
+        }
+
+        Ok(cell)
     }
 
-    fn auto_size_cols(&mut self) {
-        let num_cols = self.columns.len();
-        for col_num in 0..num_cols {
-            self.auto_size_col(col_num);
-        }
+    /// The next field after `after` that can be typed in, for filling in a new row.
+    fn next_input_field(&self, after: usize) -> Option<usize> {
+        (after + 1..=self.columns.len()).find(|field| !self.columns[field - 1].is_read_only())
     }
 
-    fn del_curr_col(&mut self) {
-        let curr_col = self.curr_col;
-        self.columns.remove(curr_col);
-        for row_num in 0..self.data.len() {
-            self.data[row_num].remove(curr_col);
-        }
+    fn col_num(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|col| col.name == name)
     }
 
-    fn move_curr_col_left(&mut self) {
-        //   < X
-        // 0 1 2 3 4
-        // 0 1 2 3 4 1 2
-        // 0 2 1 3 4
+    /// The value of a cell, computing it for computed columns.
+    fn value(&self, row: usize, col: usize, others: &OtherTables) -> Cell {
+        self.value_at_depth(row, col, others, 0)
+    }
 
-        if self.curr_col <= 0 {
-            return;
+    /// Computed columns can use other computed columns, `depth` stops that from going in circles.
+    fn value_at_depth(&self, row: usize, col: usize, others: &OtherTables, depth: usize) -> Cell {
+        const MAX_DEPTH: usize = 16;
+        if depth > MAX_DEPTH {
+            return Cell::Invalid("too deep".to_string());
         }
 
-        self.columns.push(Column {
-            name: self.columns[self.curr_col - 1].name.clone(),
-            width: self.columns[self.curr_col - 1].width,
-            column_type: self.columns[self.curr_col - 1].column_type.clone(),
-        });
-        self.columns.push(Column {
-            name: self.columns[self.curr_col].name.clone(),
-            width: self.columns[self.curr_col].width,
-            column_type: self.columns[self.curr_col].column_type.clone(),
-        });
+        let column = &self.columns[col];
+        match (&column.column_type, &column.rollup) {
+            (ColumnType::Rollup, Some(rollup)) => {
+                let ids = match self
+                    .col_num(&rollup.relation)
+                    .map(|rel| &self.data[row][rel])
+                {
+                    Some(Cell::Relation(ids)) => ids.clone(),
+                    Some(_) => vec![],
+                    None => return Cell::Invalid(rollup.relation.clone()),
+                };
+                let linked = self
+                    .col_num(&rollup.relation)
+                    .and_then(|rel| self.linked_table(&self.columns[rel].relation, others));
+                let (linked, linked_col) = match linked
+                    .and_then(|linked| linked.col_num(&rollup.column).map(|c| (linked, c)))
+                {
+                    Some(found) => found,
+                    None => return Cell::Invalid(rollup.column.clone()),
+                };
+                let values: Vec<Cell> = ids
+                    .iter()
+                    .filter_map(|id| linked.row_num(*id))
+                    .map(|linked_row| {
+                        linked.value_at_depth(linked_row, linked_col, others, depth + 1)
+                    })
+                    .collect();
+                rollup.compute(&values)
+            }
+            (ColumnType::Rollup, None) => Cell::Empty,
+            (ColumnType::Formula, _) if column.formula.is_empty() => Cell::Empty,
+            (ColumnType::Formula, _) => {
+                let lookup = |name: &str| {
+                    self.col_num(name)
+                        .map(|c| self.value_at_depth(row, c, others, depth + 1))
+                };
+                match column
+                    .parsed_formula()
+                    .as_ref()
+                    .map_err(|error| error.clone())
+                    .and_then(|expr| formula::eval(expr, &lookup))
+                {
+                    Ok(value) => value,
+                    Err(error) => Cell::Invalid(error),
+                }
+            }
+            _ => self.data[row][col].clone(),
+        }
+    }
 
-        self.columns.swap_remove(self.curr_col - 1);
-        self.columns.swap_remove(self.curr_col);
+    /// How a cell is shown, for cells whose value depends on other rows or tables.
+    fn display_cell(&self, row: usize, col: usize, others: &OtherTables) -> (String, ColorPair) {
+        let column = &self.columns[col];
+        if column.is_computed() {
+            return cell_as_col_type(&self.value(row, col, others), column);
+        }
+        match &self.data[row][col] {
+            Cell::Relation(ids) => {
+                let linked = self.linked_table(&column.relation, others);
+                let titles: Vec<String> = ids
+                    .iter()
+                    .map(|id| match linked.and_then(|linked| linked.row_num(*id)) {
+                        Some(row_num) => linked.unwrap().row_title(row_num),
+                        None => "?".to_string(),
+                    })
+                    .collect();
+                (titles.join(", "), WHITE_PAIR)
+            }
+            cell => cell_as_col_type(cell, column),
+        }
+    }
 
-        for i in 0..self.columns.len() {
-            let x = self.data[i][self.curr_col - 1].clone();
-            let y = self.data[i][self.curr_col].clone();
-            self.data[i].push(x);
-            self.data[i].push(y);
-            self.data[i].swap_remove(self.curr_col - 1);
-            self.data[i].swap_remove(self.curr_col);
+    /// Gives every cell the type of its column, values that don't fit are kept as invalid cells.
+    fn type_cells(&mut self) {
+        for col_num in 0..self.columns.len() {
+            self.type_col(col_num);
         }
+    }
 
-        self.curr_col -= 1;
+    fn type_col(&mut self, col: usize) {
+        for row in self.data.iter_mut() {
+            let cell = std::mem::replace(&mut row[col], Cell::Empty);
+            row[col] = cell.typed(&self.columns[col]);
+            self.columns[col].register_options(&row[col]);
+        }
     }
 
-    fn move_curr_col_right(&mut self) {
-        //     X >
-        // 0 1 2 3 4
-        // 0 1 2 3 4 2 3
-        // 0 1 3 2 4
-        if self.curr_col + 1 >= self.columns.len() {
-            return;
+    /// The cells that changing `col` to `new_type` would make invalid, clear or cut short, by row,
+    /// with what they are and what they would become.
+    fn conversion_losses(
+        &self,
+        col: usize,
+        new_type: &ColumnType,
+        others: &OtherTables,
+    ) -> Vec<(usize, Cell, Cell)> {
+        let column = Column {
+            column_type: new_type.clone(),
+            ..self.columns[col].clone()
+        };
+        (0..self.data.len())
+            .filter_map(|row| {
+                let cell = self.conversion_source(row, col, new_type, others);
+                let converted = cell.clone().convert(&column);
+                let lost = match (&cell, &converted) {
+                    (Cell::Empty | Cell::Invalid(_), _) => false,
+                    (Cell::Options(options), Cell::Select(_)) => options.len() > 1,
+                    (_, converted) => matches!(converted, Cell::Empty | Cell::Invalid(_)),
+                };
+                lost.then_some((row, cell, converted))
+            })
+            .collect()
+    }
+
+    /// What a cell of `col` is converted from when it changes to `new_type`. Computed values and
+    /// the titles of linked rows are kept rather than what is stored, which would be lost.
+    fn conversion_source(
+        &self,
+        row: usize,
+        col: usize,
+        new_type: &ColumnType,
+        others: &OtherTables,
+    ) -> Cell {
+        let column = &self.columns[col];
+        if matches!(new_type, ColumnType::Rollup | ColumnType::Formula) {
+            return self.data[row][col].clone();
+        }
+        match &self.data[row][col] {
+            _ if column.is_computed() => match self.value(row, col, others) {
+                // errors have no value to keep
+                Cell::Invalid(_) => Cell::Empty,
+                value => value,
+            },
+            Cell::Relation(ids) if *new_type != ColumnType::Relation => {
+                let linked = self.linked_table(&column.relation, others);
+                let titles: Vec<String> = ids
+                    .iter()
+                    .filter_map(|id| {
+                        let linked = linked?;
+                        Some(linked.row_title(linked.row_num(*id)?))
+                    })
+                    .collect();
+                if titles.is_empty() {
+                    Cell::Empty
+                } else {
+                    Cell::Text(titles.join(", "))
+                }
+            }
+            cell => cell.clone(),
         }
+    }
 
-        self.columns.push(Column {
-            name: self.columns[self.curr_col].name.clone(),
-            width: self.columns[self.curr_col].width,
-            column_type: self.columns[self.curr_col].column_type.clone(),
-        });
-        self.columns.push(Column {
-            name: self.columns[self.curr_col + 1].name.clone(),
-            width: self.columns[self.curr_col + 1].width,
-            column_type: self.columns[self.curr_col + 1].column_type.clone(),
-        });
+    /// The first row shown after the current one with `query` in one of its cells or its page, going
+    /// around to the top.
+    fn search(&self, query: &str, others: &OtherTables) -> Option<usize> {
+        let query = query.to_lowercase();
+        let len = self.shown_rows.len();
+        (1..=len)
+            .map(|offset| self.shown_rows[(self.curr_line() + offset) % len])
+            .find(|row| {
+                self.data[*row].body.to_lowercase().contains(&query)
+                    || (0..self.columns.len()).any(|col| {
+                        let shown = self.display_cell(*row, col, others).0;
+                        let stored = self.value(*row, col, others).to_string();
+                        shown.to_lowercase().contains(&query)
+                            || stored.to_lowercase().contains(&query)
+                    })
+            })
+    }
 
-        self.columns.swap_remove(self.curr_col);
-        self.columns.swap_remove(self.curr_col + 1);
+    fn set_body(&mut self, row: usize, body: &str) {
+        self.data[row].body = body.trim_end().to_string();
+        self.stamp_row(row, &[ColumnType::EditedTime]);
+    }
 
-        for i in 0..self.columns.len() {
-            let x = self.data[i][self.curr_col].clone();
-            let y = self.data[i][self.curr_col + 1].clone();
-            self.data[i].push(x);
-            self.data[i].push(y);
-            self.data[i].swap_remove(self.curr_col);
-            self.data[i].swap_remove(self.curr_col + 1);
-        }
+    fn set_cell(&mut self, row: usize, col: usize, cell: Cell) {
+        self.columns[col].register_options(&cell);
+        self.data[row][col] = cell;
+        self.stamp_row(row, &[ColumnType::EditedTime]);
+    }
 
-        self.curr_col += 1;
+    /// Sets the cells of the given time columns in `row` to now.
+    fn stamp_row(&mut self, row: usize, col_types: &[ColumnType]) {
+        let now = Local::now().fixed_offset();
+        for col in 0..self.columns.len() {
+            if col_types.contains(&self.columns[col].column_type) {
+                self.data[row][col] = Cell::DateTime(now);
+            }
+        }
     }
 
-    // fn move_curr_row_up(&mut self) {
-    //     if self.curr_row <= 0 {
-    //         return;
-    //     }
+    fn add_option(&mut self, col: usize, name: &str, color: Option<OptionColor>) {
+        self.columns[col].register_options(&Cell::Select(name.to_string()));
+        if let Some(color) = color {
+            self.recolor_option(col, name, color);
+        }
+    }
 
-    //     let x: Vec<String> = self.data[self.curr_row - 1]
-    //         .iter()
-    //         .map(|s| s.clone())
-    //         .collect();
-    //     let y: Vec<String> = self.data[self.curr_row].iter().map(|s| s.clone()).collect();
-    //     self.data.push(x);
-    //     self.data.push(y);
-    //     self.data.swap_remove(self.curr_row - 1);
-    //     self.data.swap_remove(self.curr_row);
+    fn rename_option(&mut self, col: usize, name: &str, new_name: &str) {
+        for option in self.columns[col].options.iter_mut() {
+            if option.name == name {
+                option.name = new_name.to_string();
+            }
+        }
+        for row in 0..self.data.len() {
+            let cell = match &self.data[row][col] {
+                Cell::Options(options) if options.iter().any(|option| option == name) => {
+                    Cell::Options(
+                        options
+                            .iter()
+                            .map(|option| if option == name { new_name } else { option })
+                            .map(|option| option.to_string())
+                            .collect(),
+                    )
+                }
+                Cell::Select(option) if option == name => Cell::Select(new_name.to_string()),
+                _ => continue,
+            };
+            self.set_cell(row, col, cell);
+        }
+    }
 
-    //     self.curr_row -= 1;
-    // }
+    fn move_option(&mut self, col: usize, name: &str, to: usize) {
+        if let Some(from) = self.columns[col].option_index(name) {
+            let option = self.columns[col].options.remove(from);
+            let to = to.min(self.columns[col].options.len());
+            self.columns[col].options.insert(to, option);
+        }
+    }
 
-    // fn move_curr_row_down(&mut self) {
-    //     if self.curr_row + 1 >= self.data.len() {
-    //         return;
-    //     }
+    fn recolor_option(&mut self, col: usize, name: &str, color: OptionColor) {
+        for option in self.columns[col].options.iter_mut() {
+            if option.name == name {
+                option.color = color.clone();
+            }
+        }
+    }
 
-    //     let x: Vec<String> = self.data[self.curr_row].iter().map(|s| s.clone()).collect();
-    //     let y: Vec<String> = self.data[self.curr_row + 1]
-    //         .iter()
-    //         .map(|s| s.clone())
-    //         .collect();
-    //     self.data.push(x);
-    //     self.data.push(y);
-    //     self.data.swap_remove(self.curr_row);
-    //     self.data.swap_remove(self.curr_row + 1);
+    fn del_option(&mut self, col: usize, name: &str) {
+        self.columns[col]
+            .options
+            .retain(|option| option.name != name);
+        for row in 0..self.data.len() {
+            let cell = match &self.data[row][col] {
+                Cell::Options(options) if options.iter().any(|option| option == name) => {
+                    let options: Vec<String> = options
+                        .iter()
+                        .filter(|option| *option != name)
+                        .cloned()
+                        .collect();
+                    if options.is_empty() {
+                        Cell::Empty
+                    } else {
+                        Cell::Options(options)
+                    }
+                }
+                Cell::Select(option) if option == name => Cell::Empty,
+                _ => continue,
+            };
+            self.set_cell(row, col, cell);
+        }
+    }
 
-    //     self.curr_row += 1;
-    // }
+    fn set_col_type(&mut self, col: usize, new_type: ColumnType, others: &OtherTables) {
+        let cells: Vec<Cell> = (0..self.data.len())
+            .map(|row| self.conversion_source(row, col, &new_type, others))
+            .collect();
+        self.columns[col].column_type = new_type;
+        for (row, cell) in self.data.iter_mut().zip(cells) {
+            row[col] = cell.convert(&self.columns[col]);
+            self.columns[col].register_options(&row[col]);
+        }
+    }
 
     fn del_curr_elem(&mut self) {
         _ = self.data.remove(self.curr_row);
         if self.curr_row + 1 > self.data.len() {
-            self.curr_row = self.data.len() - 1;
+            self.curr_row = self.data.len().saturating_sub(1);
         }
         self.table_focus = TableFocus::Table;
     }
 }
 
+/// Draws text being typed in over many lines, scrolled so that the line being typed is shown.
+fn draw_editor(title: &str, input_str: &str, y: i32, x: i32, width: usize, height: usize) {
+    label(
+        &format!("{} enter: new line, ctrl-d: save, esc: cancel", title),
+        y,
+        x,
+        WHITE_PAIR,
+    );
+    let lines: Vec<&str> = input_str.split('\n').collect();
+    let shown = lines.len().saturating_sub(height.saturating_sub(1));
+    for (line_num, line) in lines[shown..].iter().enumerate() {
+        let skipped = line.chars().count().saturating_sub(width);
+        let line: String = line.chars().skip(skipped).collect();
+        label(&line, y + 1 + line_num as i32, x + 2, WHITE_PAIR);
+    }
+    attron(COLOR_PAIR(INV_WHITE_PAIR));
+    addstr(" ");
+    attroff(COLOR_PAIR(INV_WHITE_PAIR));
+}
+
 fn n_of_c(n: usize, c: char) -> String {
-    std::iter::repeat(c).take(n).collect::<String>()
+    std::iter::repeat_n(c, n).collect::<String>()
 }
 
 fn fit_to_sizel(text: &str, n: usize, pad: char) -> String {
-    if n >= text.len() {
+    let len = text.chars().count();
+    if n >= len {
         let mut ret = "".to_string();
         ret.push_str(text);
-        ret.push_str(&n_of_c(n - text.len(), pad));
+        ret.push_str(&n_of_c(n - len, pad));
 
         ret
+    } else if n < 2 {
+        // too narrow to show any of the text
+        n_of_c(n, '.')
     } else {
-        format!("{}..", &text[..(n - 2)])
+        format!("{}..", text.chars().take(n - 2).collect::<String>())
+    }
+}
+
+/// Splits a command into its arguments, "quoted text" is kept as one argument.
+fn split_args(command: &str) -> Vec<String> {
+    let mut args: Vec<String> = vec![];
+    let mut arg: String = "".to_string();
+    let mut in_quotes = false;
+    let mut has_arg = false;
+    for c in command.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            ' ' if !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut arg));
+                    has_arg = false;
+                }
+            }
+            _ => {
+                arg.push(c);
+                has_arg = true;
+            }
+        }
+    }
+    if has_arg {
+        args.push(arg);
     }
+
+    args
 }
 
 fn fit_to_sizer(text: &str, n: usize, pad: char) -> String {
-    if n > text.len() {
+    let len = text.chars().count();
+    if n > len {
         let mut ret = "".to_string();
-        ret.push_str(&n_of_c(n - text.len(), pad));
+        ret.push_str(&n_of_c(n - len, pad));
         ret.push_str(text);
 
         ret
@@ -681,12 +4201,15 @@ fn fit_to_sizer(text: &str, n: usize, pad: char) -> String {
 
 #[derive(Serialize, Deserialize)]
 enum NumMode {
-    Absolute,
     Relative,
+    /// Also what an unknown mode is read as.
+    #[serde(other)]
+    Absolute,
 }
 
 fn create_default_table_file() {
     let table: Table = Table {
+        version: TABLE_VERSION,
         title: "Default Title".to_string(),
         subtitle: "Default Subtitle".to_string(),
         columns: vec![
@@ -694,56 +4217,56 @@ fn create_default_table_file() {
                 name: "String".to_string(),
                 width: 7,
                 column_type: ColumnType::String,
+                ..Default::default()
             },
             Column {
                 name: "Multiselect".to_string(),
                 width: 13,
                 column_type: ColumnType::Multiselect,
+                ..Default::default()
             },
             Column {
                 name: "Boolean".to_string(),
                 width: 8,
                 column_type: ColumnType::Boolean,
+                ..Default::default()
             },
             Column {
                 name: "Date".to_string(),
                 width: 5,
                 column_type: ColumnType::Date,
+                ..Default::default()
             },
             Column {
                 name: "Number".to_string(),
                 width: 7,
                 column_type: ColumnType::Number,
+                ..Default::default()
             },
         ],
-        data: vec![
-            vec![
-                "".to_string(),
-                "".to_string(),
-                "".to_string(),
-                "".to_string(),
-                "".to_string(),
-            ],
-            vec![
-                "".to_string(),
-                "".to_string(),
-                "".to_string(),
-                "".to_string(),
-                "".to_string(),
-            ],
-            vec![
-                "".to_string(),
-                "".to_string(),
-                "".to_string(),
-                "".to_string(),
-                "".to_string(),
-            ],
-        ],
+        data: (1..=3)
+            .map(|id| Row {
+                id,
+                cells: vec![Cell::Empty; 5],
+                ..Default::default()
+            })
+            .collect(),
+        views: vec![View::named("All")],
+        curr_view: 0,
+        next_row_id: 4,
         curr_row: 0,
+        curr_row_id: 1,
+        shown_rows: vec![],
+        groups: vec![],
+        curr_cond: 0,
+        curr_lane: 0,
+        curr_day: Local::now().date_naive(),
+        unsaved_query: Query::default(),
         curr_col: 0,
         num_mode: NumMode::Absolute,
         table_focus: TableFocus::Table,
         path: "default.json".to_string(),
+        extra: Map::new(),
     };
 
     let mut file = File::create("tables/.default.json").unwrap();
@@ -752,41 +4275,165 @@ fn create_default_table_file() {
         Ok(j) => j,
         Err(error) => panic!("Problem saving json: {:?}", error),
     };
-    writeln!(file, "{}", json);
+    _ = writeln!(file, "{}", json);
+}
+
+/// The version of the table file format that this ion saves, files from before there were
+/// versions are version 0.
+///
+/// Files from newer versions can have fields and values this one doesn't know, which are kept
+/// as they are so saving doesn't lose them. Unknown fields go in the `extra` map of the struct
+/// they're in and unknown values of an enum, like a column type, in its `Unknown` variant.
+const TABLE_VERSION: u64 = 1;
+
+/// Upgrades table files to the current format, `MIGRATIONS[n]` takes a file from version n to
+/// version n + 1. A change to the format adds one here and bumps `TABLE_VERSION`.
+const MIGRATIONS: [fn(&mut Value); TABLE_VERSION as usize] = [migrate_row_ids];
+
+/// Rows used to be stored as just their cells and the cursor as the index of its row, rows get
+/// an id and the cursor is saved by its row's id.
+fn migrate_row_ids(json: &mut Value) {
+    if !json.is_object() {
+        return;
+    }
+    let curr_row = json["curr_row"].as_u64();
+    let mut next_row_id = json["next_row_id"].as_u64().unwrap_or(0);
+    let rows = match json["data"].as_array_mut() {
+        Some(rows) => rows,
+        None => return,
+    };
+    let max_id = rows
+        .iter()
+        .filter_map(|row| row["id"].as_u64())
+        .max()
+        .unwrap_or(0);
+    next_row_id = max(next_row_id, max_id + 1);
+    for row in rows.iter_mut() {
+        if row.is_array() {
+            *row = json!({ "cells": row.take() });
+        }
+        if !row.is_object() {
+            continue;
+        }
+        if row["id"].as_u64().unwrap_or(0) == 0 {
+            row["id"] = json!(next_row_id);
+            next_row_id += 1;
+        }
+    }
+    let curr_row_id = curr_row
+        .and_then(|curr_row| rows.get(curr_row as usize))
+        .map(|row| row["id"].clone());
+
+    if let (Some(curr_row_id), None) = (curr_row_id, json.get("curr_row_id")) {
+        json["curr_row_id"] = curr_row_id;
+    }
+    json["next_row_id"] = json!(next_row_id);
+    if let Some(json) = json.as_object_mut() {
+        json.remove("curr_row");
+    }
+}
+
+/// Reads a table file of any version, upgrading it to the current one.
+fn read_table(table_str: &str) -> Result<Table> {
+    let mut json: Value = serde_json::from_str(table_str)?;
+    let version = json["version"].as_u64().unwrap_or(0);
+    for migration in MIGRATIONS.iter().skip(version as usize) {
+        migration(&mut json);
+    }
+    let mut table: Table = serde_json::from_value(json)?;
+    // files from newer versions of ion keep their version, so they aren't migrated again
+    table.version = max(table.version, TABLE_VERSION);
+    // a format that can't write dates would panic when they're drawn
+    for column in table.columns.iter_mut() {
+        if !is_valid_date_format(&column.date_format) {
+            column.date_format.clear();
+        }
+    }
+
+    Ok(table)
 }
 
 fn table_file_exists(file_str: &str) -> bool {
-    match fs::read_to_string(&format!("tables/{}", file_str)) {
-        Ok(table_str) => match serde_json::from_str::<Table>(&table_str) {
-            Ok(_) => true,
-            Err(_) => false,
-        },
+    match fs::read_to_string(format!("tables/{}", file_str)) {
+        Ok(table_str) => read_table(&table_str).is_ok(),
         Err(_) => false,
     }
 }
 
-fn load_table(file_str: &str) -> Table {
-    // TODO #29 Error catch opening files that don't exist
-    let table_str: String =
-        fs::read_to_string(&format!("tables/{}", file_str)).expect("File does not exist");
-    let res: Result<Table> = serde_json::from_str(&table_str);
-    let table: Table = match res {
+fn load_table(file_str: &str) -> std::result::Result<Table, String> {
+    let table_str = match fs::read_to_string(format!("tables/{}", file_str)) {
+        Ok(table_str) => table_str,
+        Err(_) => return Err(format!("Error: There is no file '{}'", file_str)),
+    };
+    let mut table: Table = match read_table(&table_str) {
         Ok(t) => t,
-        Err(error) => panic!("Problem reading json: {:?}", error),
+        Err(error) => return Err(format!("Error: Can't read '{}': {}", file_str, error)),
+    };
+    table.curr_row = table.row_num(table.curr_row_id).unwrap_or(0);
+    if table.views.is_empty() {
+        table.views.push(View::named("All"));
+    }
+    table.curr_view = min(table.curr_view, table.views.len() - 1);
+    table.curr_day = Local::now().date_naive();
+    table.type_cells();
+
+    Ok(table)
+}
+
+/// Opens the tables that `table`'s relations link to, and the ones those link to.
+fn open_linked_tables(table: &Table, others: &mut OtherTables) {
+    let mut to_open: Vec<String> = table
+        .columns
+        .iter()
+        .map(|col| col.relation.clone())
+        .collect();
+    while let Some(path) = to_open.pop() {
+        if path.is_empty() || path == table.path || others.contains_key(&path) {
+            continue;
+        }
+        let Ok(mut linked) = load_table(&path) else {
+            continue;
+        };
+        linked.path = path.clone();
+        to_open.extend(linked.columns.iter().map(|col| col.relation.clone()));
+        others.insert(path, linked);
+    }
+}
+
+/// Makes the table at `path` the one being looked at, the current one stays open in `others`.
+fn switch_table(
+    table: &mut Table,
+    others: &mut OtherTables,
+    path: &str,
+) -> std::result::Result<(), String> {
+    if path == table.path {
+        return Ok(());
+    }
+    let next: Table = match others.remove(path) {
+        Some(next) => next,
+        None => {
+            let mut next = load_table(path)?;
+            next.path = path.to_string();
+            next
+        }
     };
+    let prev: Table = std::mem::replace(table, next);
+    others.insert(prev.path.clone(), prev);
+    open_linked_tables(table, others);
 
-    table
+    Ok(())
 }
 
-fn save_table(table: &Table, file_str: &str) {
+fn save_table(table: &mut Table, file_str: &str) {
     // TODO prevent saving to .default.json?
-    let mut file = File::create(&format!("tables/{}", file_str)).unwrap();
+    table.curr_row_id = table.data.get(table.curr_row).map_or(0, |row| row.id);
+    let mut file = File::create(format!("tables/{}", file_str)).unwrap();
     let res = serde_json::to_string_pretty(table);
     let json = match res {
         Ok(j) => j,
         Err(error) => panic!("Problem saving json: {:?}", error),
     };
-    writeln!(file, "{}", json);
+    _ = writeln!(file, "{}", json);
 }
 
 fn main() {
@@ -811,10 +4458,19 @@ fn main() {
     init_pair(INV_CYAN_PAIR, COLOR_BLACK, COLOR_CYAN);
 
     create_default_table_file();
-    let mut table: Table = load_table(".default.json");
+    let mut table: Table = match load_table(".default.json") {
+        Ok(table) => table,
+        Err(error) => {
+            endwin();
+            eprintln!("{}", error);
+            return;
+        }
+    };
+    let mut others: OtherTables = HashMap::new();
     let mut input_mode: InputMode = InputMode::Normal;
     let mut input_str: String = "".to_string();
     let mut command_str: String = "".to_string();
+    let mut search_str: String = "".to_string();
     let mut message_str: String = "".to_string();
     let mut error_message_str: String = "".to_string();
     let mut motion_num: usize = 0;
@@ -829,27 +4485,50 @@ fn main() {
     while !quit {
         erase();
 
+        table.refresh_view(&others);
         table.draw_title();
         table.draw_subtitle();
 
         match table.table_focus {
-            TableFocus::Table => {
-                table.draw_data();
-                table.draw_views();
+            TableFocus::Table | TableFocus::View if table.is_board() => {
+                table.draw_views(motion_num, input_mode, &input_str);
+                table.draw_board(&others);
+            }
+            TableFocus::Table | TableFocus::View if table.is_calendar() => {
+                table.draw_views(motion_num, input_mode, &input_str);
+                table.draw_calendar();
+            }
+            TableFocus::Table | TableFocus::View => {
+                table.draw_data(&others);
+                table.draw_views(motion_num, input_mode, &input_str);
+                table.draw_headers();
+                table.draw_footer();
+            }
+            TableFocus::Element => table.draw_elem(motion_num, input_mode, &input_str, &others),
+            TableFocus::NewElement => {
+                table.draw_elem(motion_num, InputMode::Text, &input_str, &others)
+            }
+            TableFocus::Sort => {
+                table.draw_data(&others);
+                table.draw_sorts();
+                table.draw_headers();
+                table.draw_footer();
+            }
+            TableFocus::Filter => {
+                table.draw_data(&others);
+                table.draw_views(motion_num, input_mode, &input_str);
                 table.draw_headers();
                 table.draw_footer();
+                table.draw_filter(motion_num, input_mode, &input_str);
             }
-            TableFocus::Element => table.draw_elem(motion_num as usize, input_mode, &input_str),
-            TableFocus::NewElement => table.draw_elem(motion_num, InputMode::Text, &input_str),
             TableFocus::Column => {
                 table.draw_headers();
-                table.draw_column(motion_num as usize, input_mode, &input_str)
+                table.draw_column(motion_num, input_mode, &input_str, &others)
             }
             TableFocus::NewColumn => {
                 table.draw_headers();
-                table.draw_column(motion_num as usize, InputMode::Text, &input_str)
+                table.draw_column(motion_num, InputMode::Text, &input_str, &others)
             }
-            _ => todo!(),
         };
 
         if motion_num != 0 {
@@ -858,18 +4537,23 @@ fn main() {
         }
 
         match input_mode {
-            InputMode::Cmd => {
+            InputMode::Cmd | InputMode::Search => {
+                let prompt = if input_mode == InputMode::Cmd {
+                    ':'
+                } else {
+                    '/'
+                };
                 mv(screen_h - 1, 0);
-                addstr(&format!(":{}", command_str));
+                addstr(&format!("{}{}", prompt, command_str));
                 attron(COLOR_PAIR(INV_WHITE_PAIR));
                 addstr(" ");
                 attroff(COLOR_PAIR(INV_WHITE_PAIR));
             }
             _ => {
                 if !error_message_str.is_empty() {
-                    label(&format!("{}", error_message_str), screen_h - 1, 0, RED_PAIR);
+                    label(&error_message_str, screen_h - 1, 0, RED_PAIR);
                 } else if !message_str.is_empty() {
-                    label(&format!("{}", message_str), screen_h - 1, 0, WHITE_PAIR);
+                    label(&message_str, screen_h - 1, 0, WHITE_PAIR);
                 } else {
                     label(
                         &format!("--{}--", table.table_focus),
@@ -889,8 +4573,39 @@ fn main() {
             InputMode::Normal => match table.table_focus {
                 TableFocus::Table => match key as u8 as char {
                     ':' => input_mode = InputMode::Cmd,
+                    '/' => input_mode = InputMode::Search,
                     // 'q' | '\x1b' => quit = true,
                     // 'w' => _ = save_table(&table, "table.json"),
+                    'j' if table.is_board() => table.move_in_lane(max(motion_num, 1) as isize),
+                    'k' if table.is_board() => table.move_in_lane(-(max(motion_num, 1) as isize)),
+                    'h' if table.is_board() => table.move_lane(-(max(motion_num, 1) as isize)),
+                    'l' if table.is_board() => table.move_lane(max(motion_num, 1) as isize),
+                    'h' | 'l' | 'j' | 'k' if table.is_calendar() => {
+                        let days = match key as u8 as char {
+                            'h' => -1,
+                            'l' => 1,
+                            'j' => 7,
+                            _ => -7,
+                        };
+                        table.move_day(days * max(motion_num, 1) as i64);
+                    }
+                    'H' | 'L' if table.is_calendar() => {
+                        let by = if key as u8 as char == 'H' { -1 } else { 1 };
+                        table.move_month(by * max(motion_num, 1) as i32);
+                    }
+                    '\n' if table.is_calendar() => {
+                        if let Err(error) = table.view_day_elem(motion_num) {
+                            error_message_str = error;
+                        }
+                    }
+                    'H' | 'L' if table.is_board() => {
+                        let by = if key as u8 as char == 'H' { -1 } else { 1 };
+                        if let Err(error) = table.move_card(by) {
+                            error_message_str = error;
+                        }
+                    }
+                    // with no rows shown, there's no row to act on
+                    'd' | 'o' | '\n' if !table.has_curr_row() => {}
                     'j' => table.down(motion_num, 1),
                     'k' => table.up(motion_num as i32, 1),
                     'J' => table.down(motion_num, 10),
@@ -900,20 +4615,32 @@ fn main() {
                     'G' => table.goto_row(motion_num as i32),
                     'c' => table.to_col_mode(),
                     's' => table.to_sort_mode(),
+                    'f' => table.to_filter_mode(),
                     'v' => table.to_view_mode(),
                     // 'V' => {}
                     'n' => table.switch_num_mode(),
+                    'z' => table.toggle_curr_group(motion_num),
+                    'Z' => table.toggle_groups(),
                     'i' => {
                         table.to_new_elem_mode();
-                        motion_num = 1;
                         table.curr_row = table.data.len() - 1;
-                        input_mode = InputMode::Text;
-                        input_str = "".to_string();
-                        preserve_motion = true;
+                        match table.next_input_field(0) {
+                            Some(field) => {
+                                motion_num = field;
+                                input_mode = InputMode::Text;
+                                input_str = "".to_string();
+                                preserve_motion = true;
+                            }
+                            None => table.to_table_mode(),
+                        }
                     }
                     'd' => table.del_curr_elem(),
+                    'o' => match table.open_cell(table.curr_row, motion_num) {
+                        Ok(target) => message_str = format!("Opening '{}'", target),
+                        Err(error) => error_message_str = error,
+                    },
                     '\n' => table.view_curr_elem(),
-                    '=' => table.auto_size_cols(),
+                    '=' => table.auto_size_cols(&others),
                     '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' | '0' => {
                         motion_num = motion_num * 10 + (key as usize - 48);
                         preserve_motion = true;
@@ -930,6 +4657,65 @@ fn main() {
                     'j' => table.down(motion_num, 1),
                     'k' => table.up(motion_num as i32, 1),
                     'd' => table.del_curr_elem(),
+                    'o' => match table.open_cell(table.curr_row, motion_num) {
+                        Ok(target) => message_str = format!("Opening '{}'", target),
+                        Err(error) => error_message_str = error,
+                    },
+                    '\n' if motion_num > 0 => {
+                        input_mode = InputMode::Text;
+                        // long text is changed rather than typed again
+                        input_str = match table.data[table.curr_row].cells.get(motion_num - 1) {
+                            Some(Cell::Text(text)) if table.is_multi_line_field(motion_num) => {
+                                text.clone()
+                            }
+                            None if table.is_multi_line_field(motion_num) => {
+                                table.data[table.curr_row].body.clone()
+                            }
+                            _ => "".to_string(),
+                        };
+                        preserve_motion = true;
+                    }
+                    // opens the row's page
+                    '\n' => {
+                        motion_num = table.columns.len() + 1;
+                        input_mode = InputMode::Text;
+                        input_str = table.data[table.curr_row].body.clone();
+                        preserve_motion = true;
+                    }
+                    '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' | '0' => {
+                        motion_num = motion_num * 10 + (key as usize - 48);
+                        preserve_motion = true;
+                    }
+                    '\x08' | '\x7f' => {
+                        motion_num /= 10;
+                        preserve_motion = true;
+                    }
+                    _ => {}
+                },
+                TableFocus::Column => match key as u8 as char {
+                    ':' => input_mode = InputMode::Cmd,
+                    'q' | '\x1b' => table.to_table_mode(),
+                    'h' => table.prev_col(motion_num as i32),
+                    'l' => table.next_col(motion_num),
+                    'H' => table.move_curr_col_left(),
+                    'L' => table.move_curr_col_right(),
+                    'c' => table.to_table_mode(),
+                    '=' => table.auto_size_curr_col(&others),
+                    '+' => table.grow_curr_col(motion_num),
+                    '-' => table.shrink_curr_col(motion_num as i32),
+                    'i' => {
+                        table.to_new_col_mode();
+                        motion_num = 1;
+                        table.curr_col = table.columns.len() - 1;
+                        input_mode = InputMode::Text;
+                        input_str = "".to_string();
+                        preserve_motion = true;
+                    }
+                    'd' => {
+                        if let Err(error) = table.del_curr_col() {
+                            error_message_str = error;
+                        }
+                    }
                     '\n' if motion_num > 0 => {
                         input_mode = InputMode::Text;
                         input_str = "".to_string();
@@ -941,35 +4727,97 @@ fn main() {
                     }
                     '\x08' | '\x7f' => {
                         motion_num /= 10;
-                        preserve_motion = true;
+                        preserve_motion = true
+                    }
+                    _ => {}
+                },
+                TableFocus::Sort => match key as u8 as char {
+                    ':' => input_mode = InputMode::Cmd,
+                    'q' | '\x1b' | 's' => table.to_table_mode(),
+                    'h' => table.prev_col(motion_num as i32),
+                    'l' => table.next_col(motion_num),
+                    'a' => table.sort_curr_col(false),
+                    'd' => table.sort_curr_col(true),
+                    'x' => table.unsort_curr_col(),
+                    'X' => table.views[table.curr_view].sorts.clear(),
+                    'K' => table.move_curr_sort(1),
+                    'J' => table.move_curr_sort(-1),
+                    '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' | '0' => {
+                        motion_num = motion_num * 10 + (key as usize - 48);
+                        preserve_motion = true;
+                    }
+                    '\x08' | '\x7f' => {
+                        motion_num /= 10;
+                        preserve_motion = true
+                    }
+                    _ => {}
+                },
+                TableFocus::Filter => match key as u8 as char {
+                    ':' => input_mode = InputMode::Cmd,
+                    'q' | '\x1b' | 'f' => table.to_table_mode(),
+                    'h' => table.prev_col(motion_num as i32),
+                    'l' => table.next_col(motion_num),
+                    'j' => {
+                        table.curr_cond = min(
+                            table.curr_cond + max(motion_num, 1),
+                            table.num_conds().saturating_sub(1),
+                        )
+                    }
+                    'k' => table.curr_cond = table.curr_cond.saturating_sub(max(motion_num, 1)),
+                    // a adds to the current group, o starts a new one, both go on to the test
+                    'a' | 'o' => {
+                        table.add_cond(key as u8 as char == 'o');
+                        motion_num = 2;
+                        input_mode = InputMode::Text;
+                        input_str = "".to_string();
+                        preserve_motion = true;
+                    }
+                    'd' => table.del_curr_cond(),
+                    'X' => {
+                        table.views[table.curr_view].filter.clear();
+                        table.views[table.curr_view].query = Query::default();
+                    }
+                    '\n' if motion_num > 0 && table.num_conds() > 0 => {
+                        input_mode = InputMode::Text;
+                        input_str = "".to_string();
+                        preserve_motion = true;
+                    }
+                    '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' | '0' => {
+                        motion_num = motion_num * 10 + (key as usize - 48);
+                        preserve_motion = true;
+                    }
+                    '\x08' | '\x7f' => {
+                        motion_num /= 10;
+                        preserve_motion = true
                     }
                     _ => {}
                 },
-                TableFocus::Column => match key as u8 as char {
+                TableFocus::View => match key as u8 as char {
                     ':' => input_mode = InputMode::Cmd,
-                    'q' | '\x1b' => table.to_table_mode(),
-                    'h' => table.prev_col(motion_num as i32),
-                    'l' => table.next_col(motion_num),
-                    'H' => table.move_curr_col_left(),
-                    'L' => table.move_curr_col_right(),
-                    'c' => table.to_table_mode(),
-                    '=' => table.auto_size_curr_col(),
-                    '+' => table.grow_curr_col(motion_num),
-                    '-' => table.shrink_curr_col(motion_num as i32),
+                    'q' | '\x1b' | 'v' => table.to_table_mode(),
+                    'h' => table.prev_view(motion_num),
+                    'l' => table.next_view(motion_num),
+                    '\n' => {
+                        table.goto_view(motion_num);
+                        table.to_table_mode();
+                    }
+                    // motion 0 makes a view, 1 renames the current one
                     'i' => {
-                        table.to_new_col_mode();
-                        motion_num = 1;
-                        table.curr_col = table.columns.len() - 1;
+                        motion_num = 0;
                         input_mode = InputMode::Text;
                         input_str = "".to_string();
-                        preserve_motion = true;
                     }
-                    'd' => table.del_curr_col(),
-                    '\n' if motion_num > 0 => {
+                    'r' => {
+                        motion_num = 1;
                         input_mode = InputMode::Text;
-                        input_str = "".to_string();
+                        input_str = table.view().name.clone();
                         preserve_motion = true;
                     }
+                    'd' => {
+                        if let Err(error) = table.del_view() {
+                            error_message_str = error;
+                        }
+                    }
                     '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' | '0' => {
                         motion_num = motion_num * 10 + (key as usize - 48);
                         preserve_motion = true;
@@ -983,64 +4831,72 @@ fn main() {
                 _ => {}
             },
             InputMode::Text => match key as u8 as char {
-                '\n' => match table.table_focus {
+                '\n' if table.is_multi_line_field(motion_num) => {
+                    input_str.push('\n');
+                    preserve_motion = true;
+                }
+                // ctrl-d, saves long text
+                '\n' | '\x04' => match table.table_focus {
+                    TableFocus::Element if motion_num == table.columns.len() + 1 => {
+                        table.set_body(table.curr_row, &input_str);
+                        input_str = "".to_string();
+                        input_mode = InputMode::Normal;
+                    }
                     TableFocus::Element => {
-                        // TODO check what type the thing we are adding is (based on motion_num), change input
-                        //      pad dates (or reject if year isn't long enough)
-                        let new_data: String = match table.columns[motion_num - 1].column_type {
-                            ColumnType::Date => {
-                                let date_regex: regex::Regex =
-                                    Regex::new(r"^(\d{1,2})/(\d{1,2})/(\d{4})$").unwrap();
-                                match date_regex.captures(&input_str) {
-                                    Some(caps) => {
-                                        format!(
-                                            "{:0>2}/{:0>2}/{}",
-                                            caps.get(1).unwrap().as_str(),
-                                            caps.get(2).unwrap().as_str(),
-                                            caps.get(3).unwrap().as_str()
-                                        )
-                                    }
-                                    None => input_str.clone(),
-                                }
+                        match table.parse_input(table.curr_row, motion_num - 1, &input_str, &others)
+                        {
+                            Ok(new_data) => {
+                                table.set_cell(table.curr_row, motion_num - 1, new_data);
+                                input_str = "".to_string();
+                                input_mode = InputMode::Normal;
                             }
-                            ColumnType::Boolean if input_str.is_empty() => {
-                                if table.data[table.curr_row][motion_num - 1] == "t" {
-                                    "f".to_string()
-                                } else {
-                                    "t".to_string()
-                                }
+                            Err(error) => {
+                                error_message_str = error;
+                                preserve_motion = true;
                             }
-                            _ => input_str,
-                        };
-                        table.data[table.curr_row].push(new_data);
-                        table.data[table.curr_row].swap_remove(motion_num - 1);
-                        input_str = "".to_string();
-                        input_mode = InputMode::Normal;
+                        }
                     }
                     TableFocus::NewElement => {
                         let table_len = table.data.len();
-                        table.data[table_len - 1].push(input_str);
-                        table.data[table_len - 1].swap_remove(motion_num - 1);
-                        input_str = "".to_string();
-
-                        if motion_num < table.columns.len() {
-                            motion_num += 1;
-                            preserve_motion = true;
+                        // an empty field keeps the column's default
+                        let parsed = if input_str.is_empty() {
+                            let cell = table.data[table_len - 1][motion_num - 1].clone();
+                            table.check_cell(table_len - 1, motion_num - 1, cell)
                         } else {
-                            table.curr_row = table_len - 1;
-                            table.to_table_mode();
-                            input_mode = InputMode::Normal;
+                            table.parse_input(table_len - 1, motion_num - 1, &input_str, &others)
+                        };
+                        match parsed {
+                            Ok(new_data) => {
+                                table.set_cell(table_len - 1, motion_num - 1, new_data);
+                                input_str = "".to_string();
+
+                                match table.next_input_field(motion_num) {
+                                    Some(field) => motion_num = field,
+                                    None => {
+                                        table.curr_row = table_len - 1;
+                                        table.to_table_mode();
+                                        input_mode = InputMode::Normal;
+                                    }
+                                }
+                            }
+                            Err(error) => error_message_str = error,
                         }
+                        preserve_motion = input_mode == InputMode::Text;
                     }
                     TableFocus::Column => match motion_num {
-                        1 => {
-                            let new_str_len = input_str.len() as i32;
-                            table.columns[table.curr_col].name = input_str;
-                            table.columns[table.curr_col].width =
-                                max(table.columns[table.curr_col].width, new_str_len + 1);
-                            input_str = "".to_string();
-                            input_mode = InputMode::Normal;
-                        }
+                        1 => match table.rename_col(table.curr_col, &input_str) {
+                            Ok(()) => {
+                                let new_str_len = input_str.len() as i32;
+                                table.columns[table.curr_col].width =
+                                    max(table.columns[table.curr_col].width, new_str_len + 1);
+                                input_str = "".to_string();
+                                input_mode = InputMode::Normal;
+                            }
+                            Err(error) => {
+                                error_message_str = error;
+                                preserve_motion = true;
+                            }
+                        },
                         2 => match input_str.parse::<i32>() {
                             Ok(as_i32) => {
                                 table.columns[table.curr_col].width =
@@ -1053,13 +4909,39 @@ fn main() {
                             }
                         },
                         3 => match ColumnType::from_str(&input_str) {
-                            // TODO #33 turn into multiselect
-                            Ok(new_type) => {
-                                table.columns[table.curr_col].column_type = new_type;
+                            Ok(new_type) if !matches!(new_type, ColumnType::Unknown(_)) => {
+                                table.set_col_type(table.curr_col, new_type, &others);
                                 input_str = "".to_string();
                                 input_mode = InputMode::Normal;
                             }
-                            Err(_) => {
+                            _ => {
+                                preserve_motion = true;
+                            }
+                        },
+                        4 if matches!(
+                            table.columns[table.curr_col].column_type,
+                            ColumnType::Formula
+                        ) =>
+                        {
+                            match formula::parse(&input_str) {
+                                Err(error) if !input_str.is_empty() => {
+                                    error_message_str = format!("Formula Error: {}", error);
+                                    preserve_motion = true;
+                                }
+                                _ => {
+                                    table.columns[table.curr_col].set_formula(&input_str);
+                                    input_str = "".to_string();
+                                    input_mode = InputMode::Normal;
+                                }
+                            }
+                        }
+                        4 => match table.columns[table.curr_col].set_default(&input_str) {
+                            Ok(()) => {
+                                input_str = "".to_string();
+                                input_mode = InputMode::Normal;
+                            }
+                            Err(error) => {
+                                error_message_str = error;
                                 preserve_motion = true;
                             }
                         },
@@ -1068,12 +4950,15 @@ fn main() {
                     TableFocus::NewColumn => {
                         match motion_num {
                             1 => {
-                                if !input_str.is_empty() {
-                                    let input_len: i32 = input_str.len() as i32;
-                                    table.columns[table.curr_col].name = input_str;
-                                    table.columns[table.curr_col].width = input_len + 1;
-                                    input_str = "".to_string();
-                                    motion_num += 1;
+                                match table.check_col_name(table.curr_col, &input_str) {
+                                    Ok(()) => {
+                                        let input_len: i32 = input_str.len() as i32;
+                                        table.columns[table.curr_col].name = input_str;
+                                        table.columns[table.curr_col].width = input_len + 1;
+                                        input_str = "".to_string();
+                                        motion_num += 1;
+                                    }
+                                    Err(error) => error_message_str = error,
                                 }
                                 preserve_motion = true;
                             }
@@ -1092,20 +4977,95 @@ fn main() {
                                 preserve_motion = true;
                             }
                             3 => match ColumnType::from_str(&input_str) {
-                                // TODO #33 turn into multiselect
-                                Ok(new_type) => {
-                                    table.columns[table.curr_col].column_type = new_type;
+                                Ok(new_type) if !matches!(new_type, ColumnType::Unknown(_)) => {
+                                    table.set_col_type(table.curr_col, new_type, &others);
+                                    input_str = "".to_string();
+                                    let col = &table.columns[table.curr_col];
+                                    // everything but computed and time columns has a 4th field
+                                    if col.is_read_only() && col.column_type != ColumnType::Formula
+                                    {
+                                        table.to_table_mode();
+                                        input_mode = InputMode::Normal;
+                                    } else {
+                                        motion_num += 1;
+                                        preserve_motion = true;
+                                    }
+                                }
+                                _ => {
+                                    preserve_motion = true;
+                                }
+                            },
+                            4 if table.columns[table.curr_col].column_type
+                                == ColumnType::Formula =>
+                            {
+                                match formula::parse(&input_str) {
+                                    Err(error) if !input_str.is_empty() => {
+                                        error_message_str = format!("Formula Error: {}", error);
+                                        preserve_motion = true;
+                                    }
+                                    _ => {
+                                        table.columns[table.curr_col].set_formula(&input_str);
+                                        input_str = "".to_string();
+                                        table.to_table_mode();
+                                        input_mode = InputMode::Normal;
+                                    }
+                                }
+                            }
+                            4 => match table.columns[table.curr_col].set_default(&input_str) {
+                                Ok(()) => {
+                                    let default = table.columns[table.curr_col].default_cell();
+                                    for row in table.data.iter_mut() {
+                                        row[table.curr_col] = default.clone();
+                                    }
                                     input_str = "".to_string();
                                     table.to_table_mode();
                                     input_mode = InputMode::Normal;
                                 }
-                                Err(_) => {
+                                Err(error) => {
+                                    error_message_str = error;
                                     preserve_motion = true;
                                 }
                             },
                             _ => {}
                         };
                     }
+                    TableFocus::Filter => match table.set_cond_field(motion_num, &input_str) {
+                        Ok(()) => {
+                            input_str = "".to_string();
+                            // a test that needs a value goes on to it
+                            let needs_value = table
+                                .cond_pos(table.curr_cond)
+                                .map(|(group, pos)| &table.view().filter[group][pos])
+                                .is_some_and(|cond| cond.op.takes_value() && cond.value.is_empty());
+                            if motion_num == 2 && needs_value {
+                                motion_num = 3;
+                                preserve_motion = true;
+                            } else {
+                                input_mode = InputMode::Normal;
+                            }
+                        }
+                        Err(error) => {
+                            error_message_str = error;
+                            preserve_motion = true;
+                        }
+                    },
+                    TableFocus::View => {
+                        let done = if motion_num == 1 {
+                            table.rename_view(&input_str)
+                        } else {
+                            table.new_view(&input_str)
+                        };
+                        match done {
+                            Ok(()) => {
+                                input_str = "".to_string();
+                                input_mode = InputMode::Normal;
+                            }
+                            Err(error) => {
+                                error_message_str = error;
+                                preserve_motion = true;
+                            }
+                        }
+                    }
                     _ => {}
                 },
                 '\x1b' => {
@@ -1117,7 +5077,7 @@ fn main() {
                     preserve_motion = true;
                 } // backspace
                 _ => {
-                    input_str.push_str(&(key as u8 as char).to_string());
+                    input_str.push(key as u8 as char);
                     preserve_motion = true;
                 }
             },
@@ -1131,11 +5091,12 @@ fn main() {
                                 Some(path) => {
                                     message_str = format!("'{}' written", path);
                                     table.path = path.to_string();
-                                    save_table(&table, path);
+                                    save_table(&mut table, path);
                                 }
                                 None => {
                                     message_str = format!("'{}' written", table.path);
-                                    save_table(&table, &table.path);
+                                    let path = table.path.clone();
+                                    save_table(&mut table, &path);
                                 }
                             },
                             Some("q") | Some("quit") => match tokens.next() {
@@ -1149,7 +5110,8 @@ fn main() {
                                 None => {
                                     table.to_table_mode();
                                     message_str = format!("'{}' written", table.path);
-                                    save_table(&table, &table.path);
+                                    let path = table.path.clone();
+                                    save_table(&mut table, &path);
                                     quit = true;
                                 },
                                 Some(_) => {
@@ -1158,12 +5120,9 @@ fn main() {
                                 }
                             }
                             Some("o") | Some("open") => match tokens.next() {
-                                // TODO #29 throw error if not exist
                                 Some(path) => {
-                                    if table_file_exists(path) {
-                                        table = load_table(path);
-                                    } else {
-                                        error_message_str = format!("There is no file '{}'", path);
+                                    if let Err(error) = switch_table(&mut table, &mut others, path) {
+                                        error_message_str = error;
                                     }
                                 },
                                 None => error_message_str = "Usage Error: Insufficient arguments to '(o|open) <filepath>'".to_string(),
@@ -1187,6 +5146,266 @@ fn main() {
                                 Some(new_subtitle) => table.subtitle = new_subtitle.trim_start().to_string(),
                                 None => error_message_str = "Usage Error: Insufficient arguments to '(s|subtitle) <new-subtitle>'".to_string(),
                             }
+                            Some("opt") | Some("option") => {
+                                let args = split_args(&command_str);
+                                let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+                                let col = table.curr_col;
+                                if !table.columns[col].has_options() {
+                                    error_message_str = format!(
+                                        "Error: Column '{}' has no options",
+                                        table.columns[col].name
+                                    );
+                                } else {
+                                    match args[1..] {
+                                        ["add", name, ..] if table.columns[col].option(name).is_some() => {
+                                            error_message_str = format!("Error: Option '{}' already exists", name)
+                                        }
+                                        ["add", name] => table.add_option(col, name, None),
+                                        ["add", name, color] => match OptionColor::from_str(color) {
+                                            Ok(OptionColor::Unknown(_)) | Err(_) => error_message_str = format!("Error: Unknown color '{}'", color),
+                                            Ok(color) => table.add_option(col, name, Some(color)),
+                                        },
+                                        ["rename", name, _] if table.columns[col].option(name).is_none() => {
+                                            error_message_str = format!("Error: There is no option '{}'", name)
+                                        }
+                                        ["rename", _, new_name] if table.columns[col].option(new_name).is_some() => {
+                                            error_message_str = format!("Error: Option '{}' already exists", new_name)
+                                        }
+                                        ["rename", name, new_name] => table.rename_option(col, name, new_name),
+                                        ["color", name, _] if table.columns[col].option(name).is_none() => {
+                                            error_message_str = format!("Error: There is no option '{}'", name)
+                                        }
+                                        ["color", name, color] => match OptionColor::from_str(color) {
+                                            Ok(OptionColor::Unknown(_)) | Err(_) => error_message_str = format!("Error: Unknown color '{}'", color),
+                                            Ok(color) => table.recolor_option(col, name, color),
+                                        },
+                                        ["move", name, _] if table.columns[col].option(name).is_none() => {
+                                            error_message_str = format!("Error: There is no option '{}'", name)
+                                        }
+                                        ["move", name, position] => match position.parse::<usize>() {
+                                            Ok(position) if position > 0 => table.move_option(col, name, position - 1),
+                                            _ => error_message_str = format!("Error: '{}' is not a position", position),
+                                        },
+                                        ["del", name] if table.columns[col].option(name).is_none() => {
+                                            error_message_str = format!("Error: There is no option '{}'", name)
+                                        }
+                                        ["del", name] => table.del_option(col, name),
+                                        _ => error_message_str = "Usage Error: '(opt|option) (add <option> [color]|rename <option> <new-option>|color <option> <color>|move <option> <position>|del <option>)'".to_string(),
+                                    }
+                                }
+                            }
+                            Some("fmt") | Some("format") => {
+                                let col = table.curr_col;
+                                let spec = command_str.split_once(' ').map(|(_, spec)| spec.trim()).unwrap_or("");
+                                let shows_dates = matches!(
+                                    table.columns[col].rollup.as_ref().map(|rollup| &rollup.function),
+                                    Some(RollupFunction::Earliest | RollupFunction::Latest)
+                                );
+                                match table.columns[col].column_type {
+                                    ColumnType::Rollup if shows_dates && is_valid_date_format(spec) => {
+                                        table.columns[col].date_format = spec.to_string()
+                                    }
+                                    ColumnType::Rollup if shows_dates => error_message_str = format!("Error: Bad date format '{}'", spec),
+                                    // a formula can give numbers or dates, so it can have both formats
+                                    ColumnType::Formula if spec.contains('%') && is_valid_date_format(spec) => {
+                                        table.columns[col].date_format = spec.to_string()
+                                    }
+                                    ColumnType::Number | ColumnType::Rollup | ColumnType::Formula => match NumberFormat::from_str(spec) {
+                                        Ok(format) => table.columns[col].number_format = format,
+                                        Err(error) => error_message_str = error,
+                                    },
+                                    ColumnType::Date | ColumnType::DateRange | ColumnType::CreatedTime | ColumnType::EditedTime if is_valid_date_format(spec) => {
+                                        table.columns[col].date_format = spec.to_string()
+                                    }
+                                    ColumnType::Date | ColumnType::DateRange | ColumnType::CreatedTime | ColumnType::EditedTime => {
+                                        error_message_str = format!("Error: Bad date format '{}'", spec)
+                                    }
+                                    _ => error_message_str = format!("Error: Column '{}' has no format", table.columns[col].name),
+                                }
+                            }
+                            Some("con") | Some("constraint") => {
+                                let mut args = command_str.splitn(3, ' ').skip(1);
+                                let name = args.next().unwrap_or("");
+                                let value = args.next().unwrap_or("").trim();
+                                if let Err(error) = table.columns[table.curr_col].set_constraint(name, value) {
+                                    error_message_str = error;
+                                }
+                            }
+                            Some("filter") => {
+                                let query = command_str.split_once(' ').map(|(_, query)| query.trim()).unwrap_or("");
+                                match query {
+                                    "" => table.unsaved_query = Query::default(),
+                                    // with nothing to save the view's query is removed
+                                    "save" => {
+                                        table.views[table.curr_view].query = std::mem::take(&mut table.unsaved_query);
+                                        message_str = format!("Filter saved in view '{}'", table.view().name);
+                                    }
+                                    _ => match table.parse_query(query) {
+                                        Ok(query) => table.unsaved_query = query,
+                                        Err(error) => error_message_str = format!("Filter Error: {}", error),
+                                    },
+                                }
+                            }
+                            Some("layout") => {
+                                let args = split_args(&command_str);
+                                let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+                                match args[1..] {
+                                    ["table"] => table.views[table.curr_view].layout = Layout::Table,
+                                    ["board", name] => match table.col_num(name) {
+                                        Some(col) if table.columns[col].has_options() => {
+                                            let view = &mut table.views[table.curr_view];
+                                            view.layout = Layout::Board;
+                                            view.board_by = table.columns[col].name.clone();
+                                        }
+                                        Some(_) => error_message_str = format!("Error: Column '{}' has no options", name),
+                                        None => error_message_str = format!("Error: There is no column '{}'", name),
+                                    },
+                                    ["calendar", name] => match table.col_num(name) {
+                                        Some(col) if table.columns[col].has_dates() => {
+                                            let view = &mut table.views[table.curr_view];
+                                            view.layout = Layout::Calendar;
+                                            view.calendar_by = table.columns[col].name.clone();
+                                            table.goto_day(Some(Local::now().date_naive()));
+                                        }
+                                        Some(_) => error_message_str = format!("Error: Column '{}' has no dates", name),
+                                        None => error_message_str = format!("Error: There is no column '{}'", name),
+                                    },
+                                    _ => error_message_str = "Usage Error: 'layout (table|board <column>|calendar <column>)'".to_string(),
+                                }
+                            }
+                            Some("card") => {
+                                let name = command_str.split_once(' ').map(|(_, name)| name.trim()).unwrap_or("");
+                                let fields = &mut table.views[table.curr_view].card_fields;
+                                match table.columns.iter().find(|col| col.name == name) {
+                                    _ if name.is_empty() => fields.clear(),
+                                    Some(col) => match fields.iter().position(|field| *field == col.name) {
+                                        Some(pos) => _ = fields.remove(pos),
+                                        None => fields.push(col.name.clone()),
+                                    },
+                                    None => error_message_str = format!("Error: There is no column '{}'", name),
+                                }
+                            }
+                            Some("group") => {
+                                let name = command_str.split_once(' ').map(|(_, name)| name.trim()).unwrap_or("");
+                                match table.col_num(name) {
+                                    _ if name.is_empty() => table.views[table.curr_view].group_by.clear(),
+                                    Some(col) => {
+                                        let view = &mut table.views[table.curr_view];
+                                        view.group_by = table.columns[col].name.clone();
+                                        view.collapsed.clear();
+                                    }
+                                    None => error_message_str = format!("Error: There is no column '{}'", name),
+                                }
+                            }
+                            Some("hide") | Some("show") => {
+                                let hides = command_str.starts_with("hide");
+                                let name = command_str.split_once(' ').map(|(_, name)| name.trim()).unwrap_or("");
+                                match table.col_num(name) {
+                                    _ if name.is_empty() && hides => table.hide_col(table.curr_col),
+                                    _ if name.is_empty() => {
+                                        table.views[table.curr_view].hidden.clear();
+                                    }
+                                    Some(col) if hides => table.hide_col(col),
+                                    Some(col) => table.show_col(col),
+                                    None => error_message_str = format!("Error: There is no column '{}'", name),
+                                }
+                            }
+                            Some("view") => {
+                                let name = command_str.split_once(' ').map(|(_, name)| name.trim()).unwrap_or("");
+                                match table.view_num(name) {
+                                    Some(view_num) => table.curr_view = view_num,
+                                    None if name.is_empty() => error_message_str = "Usage Error: Insufficient arguments to 'view <name>'".to_string(),
+                                    None => error_message_str = format!("Error: There is no view '{}'", name),
+                                }
+                            }
+                            Some("wa") => {
+                                for other in others.values_mut() {
+                                    let path = other.path.clone();
+                                    save_table(other, &path);
+                                }
+                                let path = table.path.clone();
+                                save_table(&mut table, &path);
+                                message_str = format!("{} tables written", others.len() + 1);
+                            }
+                            Some("rel") | Some("relation") => match tokens.next() {
+                                Some(_) if !matches!(table.columns[table.curr_col].column_type, ColumnType::Relation) => {
+                                    error_message_str = format!("Error: Column '{}' is not a relation", table.columns[table.curr_col].name);
+                                }
+                                Some(path) if path == table.path || table_file_exists(path) => {
+                                    let col = table.curr_col;
+                                    table.columns[col].relation = path.to_string();
+                                    open_linked_tables(&table, &mut others);
+                                }
+                                Some(path) => error_message_str = format!("There is no file '{}'", path),
+                                None => error_message_str = "Usage Error: Insufficient arguments to '(rel|relation) <filepath>'".to_string(),
+                            },
+                            Some("follow") => {
+                                let field = tokens.next().map(|field| field.parse::<usize>());
+                                let link = tokens.next().map_or(Ok(1), |link| link.parse::<usize>());
+                                match (field, link) {
+                                    _ if !matches!(table.table_focus, TableFocus::Element) => {
+                                        error_message_str = "Error: 'follow' only works on an open row".to_string();
+                                    }
+                                    (Some(Ok(field)), Ok(link)) if field > 0 && field <= table.columns.len() && link > 0 => {
+                                        let col = &table.columns[field - 1];
+                                        let path = col.relation.clone();
+                                        match &table.data[table.curr_row][field - 1] {
+                                            Cell::Relation(ids) if link <= ids.len() => {
+                                                let id = ids[link - 1];
+                                                let linked_row = table
+                                                    .linked_table(&path, &others)
+                                                    .and_then(|linked| linked.row_num(id));
+                                                match linked_row {
+                                                    Some(row_num) => match switch_table(&mut table, &mut others, &path) {
+                                                        Ok(()) => {
+                                                            table.curr_row = row_num;
+                                                            table.to_table_mode();
+                                                        }
+                                                        Err(error) => error_message_str = error,
+                                                    },
+                                                    None => error_message_str = format!("Error: The linked row is no longer in '{}'", path),
+                                                }
+                                            }
+                                            _ => error_message_str = format!("Error: Field {} has no link {}", field, link),
+                                        }
+                                    }
+                                    _ => error_message_str = "Usage Error: 'follow <field> [link]'".to_string(),
+                                }
+                            }
+                            Some("rollup") => {
+                                let args = split_args(&command_str);
+                                let col = table.curr_col;
+                                match &args[1..] {
+                                    _ if !matches!(table.columns[col].column_type, ColumnType::Rollup) => {
+                                        error_message_str = format!("Error: Column '{}' is not a rollup", table.columns[col].name);
+                                    }
+                                    [relation, column, function] => {
+                                        let linked = table
+                                            .col_num(relation)
+                                            .filter(|rel| matches!(table.columns[*rel].column_type, ColumnType::Relation))
+                                            .map(|rel| table.linked_table(&table.columns[rel].relation, &others));
+                                        match (linked, RollupFunction::from_str(function)) {
+                                            (None, _) => error_message_str = format!("Error: There is no relation column '{}'", relation),
+                                            (Some(None), _) => error_message_str = format!("Error: '{}' doesn't link to a table", relation),
+                                            (Some(Some(linked)), _) if linked.col_num(column).is_none() => {
+                                                error_message_str = format!("Error: There is no column '{}' in '{}'", column, linked.path);
+                                            }
+                                            (_, Ok(RollupFunction::Unknown(_)) | Err(_)) => {
+                                                error_message_str = format!("Error: Unknown rollup function '{}'", function)
+                                            }
+                                            (_, Ok(function)) => {
+                                                table.columns[col].rollup = Some(Rollup {
+                                                    relation: relation.clone(),
+                                                    column: column.clone(),
+                                                    function,
+                                                    extra: Map::new(),
+                                                });
+                                            }
+                                        }
+                                    }
+                                    _ => error_message_str = "Usage Error: 'rollup <relation-column> <column> <function>'".to_string(),
+                                }
+                            }
                             Some("whatfile") | Some("wf") => {
                                 // XXX temp until file tree added
                                 message_str = table.path.as_str().to_string();
@@ -1206,9 +5425,33 @@ fn main() {
                         input_mode = InputMode::Normal;
                     }
                     '\x7f' => _ = command_str.pop(),
-                    _ => command_str.push_str(&(key as u8 as char).to_string()),
+                    _ => command_str.push(key as u8 as char),
                 }
             }
+            InputMode::Search => match key as u8 as char {
+                '\n' => {
+                    // an empty search looks for the last one again
+                    if !command_str.is_empty() {
+                        search_str = command_str;
+                    }
+                    if !search_str.is_empty() {
+                        match table.search(&search_str, &others) {
+                            Some(row) => table.curr_row = row,
+                            None => {
+                                error_message_str = format!("Error: No row has '{}'", search_str)
+                            }
+                        }
+                    }
+                    command_str = "".to_string();
+                    input_mode = InputMode::Normal;
+                }
+                '\x1b' => {
+                    command_str = "".to_string();
+                    input_mode = InputMode::Normal;
+                }
+                '\x7f' => _ = command_str.pop(),
+                _ => command_str.push(key as u8 as char),
+            },
         }
 
         if !preserve_motion {
@@ -1220,3 +5463,649 @@ fn main() {
 
     endwin();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_to_sizel_pads_and_cuts() {
+        assert_eq!(fit_to_sizel("ab", 4, ' '), "ab  ");
+        assert_eq!(fit_to_sizel("abcdef", 4, ' '), "ab..");
+        assert_eq!(fit_to_sizel("abcdef", 1, ' '), ".");
+        assert_eq!(fit_to_sizel("abcdef", 0, ' '), "");
+        // by chars, not bytes
+        assert_eq!(fit_to_sizel("€1,234.00", 4, ' '), "€1..");
+        assert_eq!(fit_to_sizel("€1", 4, ' '), "€1  ");
+        assert_eq!(fit_to_sizer("€1", 4, ' '), "  €1");
+    }
+
+    #[test]
+    fn only_web_links_are_opened() {
+        let url = |text: &str| open_target(&Cell::Text(text.to_string()), &ColumnType::Url);
+        assert_eq!(url("example.com"), Some("https://example.com".to_string()));
+        assert_eq!(
+            url("HTTP://example.com"),
+            Some("HTTP://example.com".to_string())
+        );
+        assert_eq!(url("file:///etc/passwd"), None);
+        assert_eq!(url("javascript://alert(1)"), None);
+        assert!(!is_valid_contact("file:///etc/passwd", &ColumnType::Url));
+        assert_eq!(
+            open_target(
+                &Cell::Text("me@example.com".to_string()),
+                &ColumnType::Email
+            ),
+            Some("mailto:me@example.com".to_string())
+        );
+        assert_eq!(
+            open_target(
+                &Cell::Text("+1 (555) 123-4567".to_string()),
+                &ColumnType::Phone
+            ),
+            Some("tel:+15551234567".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_number_reads_written_numbers() {
+        assert_eq!(parse_number("1,234.5"), Some(1234.5));
+        assert_eq!(parse_number("-$12.50"), Some(-12.5));
+        assert_eq!(parse_number("$-12.50"), Some(-12.5));
+        assert_eq!(parse_number("15%"), Some(0.15));
+        assert_eq!(parse_number("Hello World"), None);
+        assert_eq!(parse_number("--5"), None);
+        assert_eq!(parse_number("inf"), None);
+        assert_eq!(parse_number("1e999"), None);
+    }
+
+    #[test]
+    fn numbers_keep_digits_floats_lose() {
+        let table = table(
+            json!([{"name": "Count", "width": 8, "column_type": "number"}]),
+            json!([
+                {"id": 1, "cells": ["12345678901234567891"]},
+                {"id": 2, "cells": ["0.10000000000000000001"]},
+                {"id": 3, "cells": ["007.50"]},
+            ]),
+        );
+        let saved = serde_json::to_value(&table).unwrap();
+        let cells: Vec<&Value> = (0..3).map(|row| &saved["data"][row]["cells"][0]).collect();
+        assert_eq!(
+            cells,
+            ["12345678901234567891", "0.10000000000000000001", "7.5"]
+        );
+        assert_eq!(
+            cell_as_col_type(&table.data[0][0], &table.columns[0]).0,
+            "12345678901234567891"
+        );
+        assert!(matches!(table.data[0][0], Cell::Number(..)));
+    }
+
+    #[test]
+    fn number_format_round_trips() {
+        for spec in ["", "0", "0.00", "#,##0", "$#,##0.00", "0.0%"] {
+            let format = NumberFormat::from_str(spec).unwrap();
+            assert_eq!(format.to_string(), spec);
+        }
+        assert!(NumberFormat::from_str("abc").is_err());
+        assert!(NumberFormat::from_str("0.0x").is_err());
+    }
+
+    #[test]
+    fn number_format_formats() {
+        let format = |spec: &str, num: f64| NumberFormat::from_str(spec).unwrap().format(num);
+        assert_eq!(format("", 1234.5), "1234.5");
+        assert_eq!(format("0", 2.5), "2");
+        assert_eq!(format("$#,##0.00", -1234567.891), "-$1,234,567.89");
+        assert_eq!(format("0.0%", 0.125), "12.5%");
+        assert_eq!(format("0.00", -0.001), "0.00");
+        // what's shown reads back as the same number
+        assert_eq!(parse_number(&format("$#,##0.00", 1234.5)), Some(1234.5));
+    }
+
+    /// A table read from `columns` and `data` the way table files are, showing its rows in a
+    /// plain view.
+    fn table(columns: Value, data: Value) -> Table {
+        table_with_view(columns, data, json!({"name": "All"}))
+    }
+
+    /// A table like `table`, looked at through `view`.
+    fn table_with_view(columns: Value, data: Value, view: Value) -> Table {
+        let json = json!({
+            "version": TABLE_VERSION,
+            "title": "T",
+            "subtitle": "",
+            "columns": columns,
+            "data": data,
+            "views": [view],
+            "curr_col": 0,
+            "num_mode": "Absolute",
+            "table_focus": "Table",
+            "path": "t.json",
+        });
+        let mut table = read_table(&json.to_string()).unwrap();
+        table.type_cells();
+        table.refresh_view(&HashMap::new());
+
+        table
+    }
+
+    #[test]
+    fn version_0_files_are_migrated() {
+        // rows were just their cells, and the cursor was the current row's index
+        let v0 = json!({
+            "title": "T",
+            "subtitle": "",
+            "columns": [{"name": "Name", "width": 8, "column_type": "string"}],
+            "data": [["a"], ["b"], ["c"]],
+            "curr_row": 1,
+            "curr_col": 0,
+            "num_mode": "Absolute",
+            "table_focus": "Table",
+            "path": "t.json",
+        });
+        let table = read_table(&v0.to_string()).unwrap();
+        assert_eq!(table.version, TABLE_VERSION);
+        let ids: Vec<RowId> = table.data.iter().map(|row| row.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(table.curr_row_id, 2);
+        assert_eq!(table.next_row_id, 4);
+
+        let saved = serde_json::to_string(&table).unwrap();
+        let again = read_table(&saved).unwrap();
+        assert_eq!(serde_json::to_string(&again).unwrap(), saved);
+    }
+
+    #[test]
+    fn unknown_fields_and_values_survive_saving() {
+        let newer = json!({
+            "version": TABLE_VERSION + 1,
+            "title": "T",
+            "subtitle": "",
+            "columns": [
+                {
+                    "name": "Tags",
+                    "width": 8,
+                    "column_type": "multiselect",
+                    "options": [{"name": "a", "color": "orange", "icon": "*"}],
+                    "constraints": {"required": true, "max_count": 2},
+                },
+                {"name": "Map", "width": 8, "column_type": "location", "zoom": 3},
+                {
+                    "name": "Count",
+                    "width": 8,
+                    "column_type": "rollup",
+                    "rollup": {"relation": "Tags", "column": "Tags", "function": "median", "x": 1},
+                },
+            ],
+            "views": [{
+                "name": "All",
+                "layout": "timeline",
+                "sorts": [{"column": "Tags", "nulls": "first"}],
+                "filter": [[{"column": "Tags", "op": "matches", "value": "a", "y": 2}]],
+            }],
+            "data": [{"id": 1, "cells": [["a"], "52.5, 13.4", ""]}],
+            "curr_col": 0,
+            "num_mode": "Sideways",
+            "table_focus": "Timeline",
+            "path": "t.json",
+            "theme": "dark",
+        });
+        let mut table = read_table(&newer.to_string()).unwrap();
+        table.type_cells();
+        assert!(table.columns[1].column_type == ColumnType::Unknown("location".to_string()));
+        assert!(table.columns[1].is_read_only());
+        assert!(!table.is_board() && !table.is_calendar());
+        table.refresh_view(&HashMap::new());
+        assert_eq!(table.shown_rows, vec![0]);
+
+        let saved = serde_json::to_value(&table).unwrap();
+        assert_eq!(saved["version"], TABLE_VERSION + 1);
+        assert_eq!(saved["theme"], "dark");
+        let columns = &saved["columns"];
+        assert_eq!(columns[0]["options"][0], newer["columns"][0]["options"][0]);
+        assert_eq!(
+            columns[0]["constraints"],
+            newer["columns"][0]["constraints"]
+        );
+        assert_eq!(columns[1]["column_type"], "location");
+        assert_eq!(columns[1]["zoom"], 3);
+        assert_eq!(columns[2]["rollup"], newer["columns"][2]["rollup"]);
+        let view = &saved["views"][0];
+        assert_eq!(view["layout"], "timeline");
+        assert_eq!(view["sorts"], newer["views"][0]["sorts"]);
+        assert_eq!(view["filter"], newer["views"][0]["filter"]);
+        assert_eq!(saved["data"][0]["cells"][1], "52.5, 13.4");
+    }
+
+    #[test]
+    fn mixed_values_sort_in_a_total_order() {
+        let date = |d| Cell::Date(NaiveDate::from_ymd_opt(2023, 6, d).unwrap());
+        let date_time = |d, h| {
+            Cell::DateTime(
+                Local
+                    .with_ymd_and_hms(2023, 6, d, h, 0, 0)
+                    .unwrap()
+                    .fixed_offset(),
+            )
+        };
+        let cells = vec![
+            Cell::number(10.0),
+            Cell::number(9.0),
+            Cell::number(f64::NAN),
+            Cell::Text("10".to_string()),
+            Cell::Text("a".to_string()),
+            Cell::Boolean(true),
+            date(23),
+            date_time(23, 9),
+            date_time(23, 11),
+            date(24),
+            Cell::DateRange(
+                NaiveDate::from_ymd_opt(2023, 6, 22).unwrap(),
+                NaiveDate::from_ymd_opt(2023, 6, 25).unwrap(),
+            ),
+            Cell::Invalid("x".to_string()),
+            Cell::Empty,
+        ];
+        let column = Column::default();
+        for a in &cells {
+            for b in &cells {
+                let ab = column.cmp_values(a, b);
+                assert_eq!(ab, column.cmp_values(b, a).reverse());
+                for c in &cells {
+                    if ab.is_le() && column.cmp_values(b, c).is_le() {
+                        assert!(column.cmp_values(a, c).is_le());
+                    }
+                }
+            }
+        }
+        let mut sorted = cells.clone();
+        sorted.sort_by(|a, b| column.cmp_values(a, b));
+        assert_eq!(sorted[0], Cell::Boolean(true));
+        assert_eq!(&sorted[1..3], &[Cell::number(9.0), Cell::number(10.0)]);
+        assert_eq!(
+            &sorted[5..8],
+            &[date(23), date_time(23, 9), date_time(23, 11)]
+        );
+    }
+
+    #[test]
+    fn within_too_many_days_is_rejected() {
+        let table = table_with_view(
+            json!([{"name": "Due", "width": 8, "column_type": "date"}]),
+            json!([{"id": 1, "cells": ["06/23/2023"]}]),
+            json!({
+                "name": "Soon",
+                "filter": [[{"column": "Due", "op": "within", "value": "4000000000"}]],
+            }),
+        );
+        let column = &table.columns[0];
+        assert!(column.check_filter_value(&FilterOp::Within, "7").is_ok());
+        assert!(column
+            .check_filter_value(&FilterOp::Within, "4000000000")
+            .is_err());
+        assert!(table.shown_rows.is_empty());
+        assert!(!table.has_curr_row());
+    }
+
+    #[test]
+    fn queries_filter_rows() {
+        let mut table = table(
+            json!([
+                {"name": "Date", "width": 8, "column_type": "date"},
+                {"name": "Count", "width": 8, "column_type": "number"},
+            ]),
+            json!([
+                {"id": 1, "cells": ["06/23/2023", "1"]},
+                {"id": 2, "cells": ["06/25/2023", "5"]},
+            ]),
+        );
+        assert!(table.parse_query("Missing > 1").is_err());
+        assert!(table.parse_query("Count >").is_err());
+
+        table.unsaved_query = table.parse_query("Count > 2").unwrap();
+        table.refresh_view(&HashMap::new());
+        assert_eq!(table.shown_rows, vec![1]);
+
+        // out of range date math is an error, which no row passes
+        table.unsaved_query = table.parse_query("Date < today + 100000000d").unwrap();
+        table.refresh_view(&HashMap::new());
+        assert!(table.shown_rows.is_empty());
+    }
+
+    #[test]
+    fn saved_queries_are_parsed_on_load() {
+        let view: View =
+            serde_json::from_value(json!({"name": "Big", "query": "Count > 2"})).unwrap();
+        assert!(view.query.expr.is_some());
+        assert_eq!(serde_json::to_value(&view).unwrap()["query"], "Count > 2");
+
+        let view: View = serde_json::from_value(json!({"name": "All"})).unwrap();
+        assert!(view.query.expr.is_none());
+        assert!(serde_json::to_value(&view).unwrap().get("query").is_none());
+    }
+
+    #[test]
+    fn queries_follow_their_columns() {
+        let mut table = table_with_view(
+            json!([
+                {"name": "Date", "width": 8, "column_type": "date"},
+                {"name": "Count", "width": 8, "column_type": "number"},
+            ]),
+            json!([
+                {"id": 1, "cells": ["06/23/2023", "1"]},
+                {"id": 2, "cells": ["06/25/2023", "5"]},
+            ]),
+            json!({"name": "Big", "query": "Count > 2"}),
+        );
+        table.rename_col(1, "Item Count").unwrap();
+        assert_eq!(table.view().query.text, "[Item Count] > 2");
+        table.refresh_view(&HashMap::new());
+        assert_eq!(table.shown_rows, vec![1]);
+
+        table.curr_col = 0;
+        table.del_curr_col().unwrap();
+        assert_eq!(table.view().query.text, "[Item Count] > 2");
+        table.del_curr_col().unwrap();
+        assert!(table.view().query.is_empty());
+    }
+
+    #[test]
+    fn toggling_groups_goes_by_the_groups_shown() {
+        // "gone" is a value no row has anymore
+        let mut table = table_with_view(
+            json!([{"name": "Done", "width": 8, "column_type": "boolean"}]),
+            json!([
+                {"id": 1, "cells": ["t"]},
+                {"id": 2, "cells": ["f"]},
+            ]),
+            json!({"name": "All", "group_by": "Done", "collapsed": ["gone"]}),
+        );
+        let others = HashMap::new();
+        assert_eq!(table.shown_rows.len(), 2);
+
+        table.toggle_groups();
+        table.refresh_view(&others);
+        assert!(table.shown_rows.is_empty());
+        assert!(!table.has_curr_row());
+
+        table.toggle_groups();
+        table.refresh_view(&others);
+        assert_eq!(table.shown_rows.len(), 2);
+        assert!(table.view().collapsed.is_empty());
+    }
+
+    #[test]
+    fn an_empty_lane_has_no_current_row() {
+        let mut table = table_with_view(
+            json!([{
+                "name": "Status",
+                "width": 8,
+                "column_type": "select",
+                "options": [{"name": "Todo", "color": "red"}, {"name": "Done", "color": "green"}],
+            }]),
+            json!([{"id": 1, "cells": ["Todo"]}]),
+            json!({"name": "Board", "layout": "board", "board_by": "Status"}),
+        );
+        assert!(table.has_curr_row());
+        table.move_lane(1);
+        assert_eq!(table.curr_lane, 1);
+        assert!(!table.has_curr_row());
+    }
+
+    #[test]
+    fn an_empty_day_has_no_current_row() {
+        let mut table = table_with_view(
+            json!([{"name": "Due", "width": 8, "column_type": "date"}]),
+            json!([{"id": 1, "cells": ["06/23/2023"]}]),
+            json!({"name": "Calendar", "layout": "calendar", "calendar_by": "Due"}),
+        );
+        table.goto_day(NaiveDate::from_ymd_opt(2023, 6, 23));
+        assert!(table.has_curr_row());
+        table.move_day(1);
+        assert!(!table.has_curr_row());
+        table.move_month(-1);
+        assert!(!table.has_curr_row());
+    }
+
+    #[test]
+    fn renaming_a_relation_keeps_its_rollups() {
+        let mut table = table(
+            json!([
+                {"name": "Tasks", "width": 8, "column_type": "relation", "relation": "t.json"},
+                {
+                    "name": "Count",
+                    "width": 8,
+                    "column_type": "rollup",
+                    "rollup": {"relation": "Tasks", "column": "Tasks", "function": "count"},
+                },
+            ]),
+            json!([]),
+        );
+        table.rename_col(0, "Subtasks").unwrap();
+        assert_eq!(
+            table.columns[1].rollup.as_ref().unwrap().relation,
+            "Subtasks"
+        );
+    }
+
+    #[test]
+    fn renaming_a_column_rewrites_formulas() {
+        let mut table = table(
+            json!([
+                {"name": "Count", "width": 8, "column_type": "number"},
+                {
+                    "name": "Double",
+                    "width": 8,
+                    "column_type": "formula",
+                    "formula": "Count * 2",
+                },
+            ]),
+            json!([{"id": 1, "cells": ["3", ""]}]),
+        );
+        assert!(table.rename_col(0, " ").is_err());
+        assert!(table.rename_col(0, "Double").is_err());
+        table.rename_col(0, "Count").unwrap();
+        table.rename_col(0, "Item Count").unwrap();
+        assert_eq!(table.columns[1].formula, "[Item Count] * 2");
+        assert_eq!(table.value(0, 1, &HashMap::new()), Cell::number(6.0));
+        table.curr_col = 0;
+        assert!(table.del_curr_col().is_err());
+        assert_eq!(table.columns.len(), 2);
+        table.curr_col = 1;
+        assert!(table.del_curr_col().is_ok());
+        table.curr_col = 0;
+        assert!(table.del_curr_col().is_ok());
+    }
+
+    #[test]
+    fn changing_options_stamps_edited_time() {
+        let mut table = table(
+            json!([
+                {
+                    "name": "Tags",
+                    "width": 8,
+                    "column_type": "multiselect",
+                    "options": [{"name": "a", "color": "red"}, {"name": "b", "color": "red"}],
+                },
+                {"name": "Edited", "width": 8, "column_type": "editedtime"},
+            ]),
+            json!([
+                {"id": 1, "cells": [["a", "b"], ""]},
+                {"id": 2, "cells": [["b"], ""]},
+            ]),
+        );
+        table.rename_option(0, "a", "c");
+        assert_eq!(
+            table.data[0][0],
+            Cell::Options(vec!["c".to_string(), "b".to_string()])
+        );
+        assert!(matches!(table.data[0][1], Cell::DateTime(_)));
+        assert_eq!(table.data[1][1], Cell::Empty);
+
+        table.del_option(0, "b");
+        assert_eq!(table.data[1][0], Cell::Empty);
+        assert!(matches!(table.data[1][1], Cell::DateTime(_)));
+    }
+
+    #[test]
+    fn bad_patterns_are_errors() {
+        let mut column = Column {
+            name: "Code".to_string(),
+            ..Default::default()
+        };
+        assert!(column.set_constraint("pattern", "a(").is_err());
+        column.set_constraint("pattern", "[a-z]+").unwrap();
+        assert!(column
+            .check_constraints(&Cell::Text("abc".to_string()))
+            .is_ok());
+        assert!(column
+            .check_constraints(&Cell::Text("abc1".to_string()))
+            .is_err());
+
+        let table = table(
+            json!([{
+                "name": "Code",
+                "width": 8,
+                "column_type": "string",
+                "constraints": {"pattern": "a("},
+            }]),
+            json!([{"id": 1, "cells": [""]}]),
+        );
+        assert!(table.parse_input(0, 0, "a", &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn multiselects_keep_their_first_option_as_selects() {
+        let table = table(
+            json!([{
+                "name": "Tags",
+                "width": 8,
+                "column_type": "multiselect",
+                "options": [{"name": "a", "color": "red"}, {"name": "b", "color": "red"}],
+            }]),
+            json!([
+                {"id": 1, "cells": [["a", "b"]]},
+                {"id": 2, "cells": [["b"]]},
+            ]),
+        );
+        let select = Column {
+            column_type: ColumnType::Select,
+            ..table.columns[0].clone()
+        };
+        assert_eq!(
+            table.data[0][0].clone().convert(&select),
+            Cell::Select("a".to_string())
+        );
+        assert_eq!(
+            table.conversion_losses(0, &ColumnType::Select, &HashMap::new()),
+            vec![(
+                0,
+                Cell::Options(vec!["a".to_string(), "b".to_string()]),
+                Cell::Select("a".to_string())
+            )]
+        );
+    }
+
+    #[test]
+    fn computed_values_and_links_survive_conversion() {
+        let mut table = table(
+            json!([
+                {"name": "Name", "width": 8, "column_type": "string"},
+                {"name": "Count", "width": 8, "column_type": "number"},
+                {
+                    "name": "Double",
+                    "width": 8,
+                    "column_type": "formula",
+                    "formula": "Count * 2",
+                },
+                {"name": "Parent", "width": 8, "column_type": "relation", "relation": "t.json"},
+            ]),
+            json!([
+                {"id": 1, "cells": ["a", "1", "", []]},
+                {"id": 2, "cells": ["b", "2", "", [1, 3]]},
+            ]),
+        );
+        let others = HashMap::new();
+        assert!(table
+            .conversion_losses(2, &ColumnType::Number, &others)
+            .is_empty());
+        table.set_col_type(2, ColumnType::Number, &others);
+        assert_eq!(table.data[1][2], Cell::number(4.0));
+
+        // links to rows that are gone are dropped
+        table.set_col_type(3, ColumnType::String, &others);
+        assert_eq!(table.data[0][3], Cell::Empty);
+        assert_eq!(table.data[1][3], Cell::Text("a".to_string()));
+    }
+
+    fn date_column(column_type: ColumnType, date_format: &str) -> Column {
+        Column {
+            column_type,
+            date_format: date_format.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Saves `cell` the way table files do and loads it back into `column`.
+    fn round_trip(cell: &Cell, column: &Column) -> Cell {
+        Cell::Text(cell.to_string()).typed(column)
+    }
+
+    #[test]
+    fn dates_round_trip_whatever_the_format() {
+        let day_first = date_column(ColumnType::Date, "%d/%m/%Y");
+        let march_4 = Cell::Date(NaiveDate::from_ymd_opt(2023, 3, 4).unwrap());
+        assert_eq!(round_trip(&march_4, &day_first), march_4);
+        let june_23 = Cell::Date(NaiveDate::from_ymd_opt(2023, 6, 23).unwrap());
+        assert_eq!(round_trip(&june_23, &day_first), june_23);
+
+        let range = Cell::DateRange(
+            NaiveDate::from_ymd_opt(2023, 3, 4).unwrap(),
+            NaiveDate::from_ymd_opt(2023, 6, 23).unwrap(),
+        );
+        let range_column = date_column(ColumnType::DateRange, "%d/%m/%Y");
+        assert_eq!(round_trip(&range, &range_column), range);
+    }
+
+    #[test]
+    fn date_formats_without_dates_are_dropped_on_load() {
+        let table = table(
+            json!([{"name": "Due", "width": 8, "column_type": "date", "date_format": "%H:%M"}]),
+            json!([{"id": 1, "cells": ["06/23/2023"]}]),
+        );
+        assert_eq!(table.columns[0].date_format(), DATE_FORMAT);
+        assert_eq!(
+            cell_as_col_type(&table.data[0][0], &table.columns[0]).0,
+            "06/23/2023"
+        );
+    }
+
+    #[test]
+    fn date_times_keep_their_seconds() {
+        let column = date_column(ColumnType::Date, "");
+        let date_time =
+            Cell::DateTime(DateTime::parse_from_rfc3339("2023-06-23T14:30:15+02:00").unwrap());
+        assert_eq!(round_trip(&date_time, &column), date_time);
+        // stored before seconds were
+        assert_eq!(
+            Cell::parse_stored("06/23/2023 14:30 +02:00", &column),
+            Cell::DateTime(DateTime::parse_from_rfc3339("2023-06-23T14:30:00+02:00").unwrap())
+        );
+    }
+
+    #[test]
+    fn dates_are_typed_in_the_column_format_first() {
+        let day_first = date_column(ColumnType::Date, "%d/%m/%Y");
+        let date = |y, m, d| Cell::Date(NaiveDate::from_ymd_opt(y, m, d).unwrap());
+        assert_eq!(Cell::parse("03/04/2023", &day_first), date(2023, 4, 3));
+        assert_eq!(Cell::parse("6/23/2023", &day_first), date(2023, 6, 23));
+        assert_eq!(Cell::parse("2023-06-23", &day_first), date(2023, 6, 23));
+        assert_eq!(Cell::parse("June 23, 2023", &day_first), date(2023, 6, 23));
+        assert_eq!(
+            Cell::parse("23/23/2023", &day_first),
+            Cell::Invalid("23/23/2023".to_string())
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! Draws the markdown of long text cells with ncurses attributes.
+//!
+//! Only the basics are styled: `# headings`, `**bold**`, `*italic*`, `` `code` ``, fenced code
+//! blocks, `> quotes` and `-`/`*`/`1.` lists. Everything else is drawn as it was typed.
+
+use crate::CYAN_PAIR;
+use ncurses::*;
+
+type StyledChar = (char, attr_t);
+
+fn code_attr() -> attr_t {
+    COLOR_PAIR(CYAN_PAIR)
+}
+
+/// Styles `**bold**`, `*italic*` and `` `code` `` in a line, markers without a closing one are
+/// kept as text.
+fn inline(text: &str, base: attr_t) -> Vec<StyledChar> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut styled: Vec<StyledChar> = vec![];
+    let mut attr = base;
+    let mut i = 0;
+    while i < chars.len() {
+        let (marker, style) = match chars[i] {
+            '`' => ("`", code_attr()),
+            '*' if chars.get(i + 1) == Some(&'*') => ("**", A_BOLD()),
+            '*' => ("*", A_UNDERLINE()),
+            c => {
+                styled.push((c, attr));
+                i += 1;
+                continue;
+            }
+        };
+        let marker: Vec<char> = marker.chars().collect();
+        let opened = attr & style != 0;
+        let closes_later = (i + marker.len()..chars.len()).any(|j| chars[j..].starts_with(&marker));
+        if opened || closes_later {
+            attr ^= style;
+        } else {
+            styled.extend(marker.iter().map(|c| (*c, attr)));
+        }
+        i += marker.len();
+        // nothing is styled inside code
+        if style == code_attr() && attr & style != 0 {
+            while i < chars.len() && chars[i] != '`' {
+                styled.push((chars[i], attr));
+                i += 1;
+            }
+        }
+    }
+
+    styled
+}
+
+/// The styled text of each line, and how far its wrapped lines are indented.
+fn style_lines(text: &str) -> Vec<(Vec<StyledChar>, usize)> {
+    let mut lines = vec![];
+    let mut in_code = false;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            lines.push((line.chars().map(|c| (c, code_attr())).collect(), 0));
+            continue;
+        }
+
+        let heading = trimmed.chars().take_while(|c| *c == '#').count();
+        let number = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+        let (prefix, rest, base) = if heading > 0 && trimmed[heading..].starts_with(' ') {
+            (
+                "".to_string(),
+                &trimmed[heading + 1..],
+                A_BOLD() | A_UNDERLINE(),
+            )
+        } else if ["- ", "* ", "+ "]
+            .iter()
+            .any(|bullet| trimmed.starts_with(bullet))
+        {
+            (
+                format!("{}* ", " ".repeat(indent)),
+                &trimmed[2..],
+                A_NORMAL(),
+            )
+        } else if number > 0 && trimmed[number..].starts_with(". ") {
+            let prefix = format!("{}{} ", " ".repeat(indent), &trimmed[..number + 1]);
+            (prefix, &trimmed[number + 2..], A_NORMAL())
+        } else if let Some(quote) = trimmed.strip_prefix("> ") {
+            ("| ".to_string(), quote, A_DIM())
+        } else {
+            (" ".repeat(indent), trimmed, A_NORMAL())
+        };
+        let mut styled: Vec<StyledChar> = prefix.chars().map(|c| (c, base)).collect();
+        styled.extend(inline(rest, base));
+        lines.push((styled, prefix.len()));
+    }
+
+    lines
+}
+
+/// Breaks a line at spaces so that no part is wider than `width`.
+fn wrap(line: &[StyledChar], width: usize, indent: usize) -> Vec<Vec<StyledChar>> {
+    let mut parts: Vec<Vec<StyledChar>> = vec![];
+    let mut rest: Vec<StyledChar> = line.to_vec();
+    let mut first = true;
+    while !rest.is_empty() || first {
+        let available = if first {
+            width
+        } else {
+            width.saturating_sub(indent)
+        }
+        .max(1);
+        let mut part: Vec<StyledChar> = if first {
+            vec![]
+        } else {
+            vec![(' ', A_NORMAL()); indent.min(width - 1)]
+        };
+        if rest.len() <= available {
+            part.append(&mut rest);
+        } else {
+            let cut = rest[..=available]
+                .iter()
+                .rposition(|(c, _)| *c == ' ')
+                .filter(|cut| *cut > 0)
+                .unwrap_or(available);
+            let tail = rest.split_off(cut);
+            part.append(&mut rest);
+            rest = tail;
+            if rest.first().is_some_and(|(c, _)| *c == ' ') {
+                rest.remove(0);
+            }
+        }
+        parts.push(part);
+        first = false;
+    }
+
+    parts
+}
+
+/// Draws `text` at `y`, `x` in at most `width` columns and `height` lines, returning how many
+/// lines it took.
+pub fn draw(text: &str, y: i32, x: i32, width: usize, height: usize) -> usize {
+    let mut used = 0;
+    for (line, indent) in style_lines(text) {
+        for part in wrap(&line, width, indent) {
+            if used == height {
+                return used;
+            }
+            mv(y + used as i32, x);
+            for (c, attr) in part {
+                attron(attr);
+                addstr(&c.to_string());
+                attroff(attr);
+            }
+            used += 1;
+        }
+    }
+
+    used
+}